@@ -1,11 +1,22 @@
 use crate::{
-    api::{ApiResource, Exec},
-    conn::{tty, Headers, Payload},
-    models, opts, Result, Stream, TryStreamExt, Value,
+    api::{ApiResource, Exec, InteractiveSession},
+    body::reader_stream,
+    conn::{hyper::Body, tty, Headers, Payload},
+    copy, models, opts, Error, Result, Stream, TryStreamExt, Value,
 };
 
+use bytes::Bytes;
+use futures_util::{AsyncRead, AsyncWrite, AsyncWriteExt, StreamExt};
+
 use containers_api::url;
-use std::path::Path;
+use std::{
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+use tokio::sync::mpsc;
 
 impl_api_ty!(
     Container => id
@@ -410,6 +421,47 @@ impl Container {
         )
     }}
 
+    api_doc! {
+    Container => CheckpointLibpod
+    |
+    /// Checkpoint this container and write the exported checkpoint tar.gz to the file at `path`
+    /// as it is received. The archive can be restored on another host with
+    /// [`Containers::restore_from_archive`](Containers::restore_from_archive).
+    ///
+    /// Examples:
+    ///
+    /// ```no_run
+    /// async {
+    ///     use podman_api::Podman;
+    ///     use podman_api::opts::ContainerCheckpointOpts;
+    ///     let podman = Podman::unix("/run/user/1000/podman/podman.sock");
+    ///
+    ///     match podman
+    ///         .containers()
+    ///         .get("79c93f220e3e")
+    ///         .checkpoint_to_path("/tmp/79c93f220e3e.tar.gz", &ContainerCheckpointOpts::default())
+    ///         .await
+    ///     {
+    ///         Ok(exported) => println!("{} {}", exported.size, exported.digest),
+    ///         Err(e) => eprintln!("{}", e),
+    ///     }
+    /// };
+    /// ```
+    pub async fn checkpoint_to_path(
+        &self,
+        path: impl AsRef<Path>,
+        opts: &opts::ContainerCheckpointOpts,
+    ) -> Result<models::ExportedArchive> {
+        let ep = url::construct_ep(
+            format!("/libpod/containers/{}/checkpoint", &self.id),
+            opts.for_export().serialize(),
+        );
+        let stream = self
+            .podman
+            .post_stream(ep, Payload::empty(), Headers::none());
+        crate::body::write_to_path(stream, path.as_ref(), false).await
+    }}
+
     api_doc! {
     Container => CheckpointLibpod
     |
@@ -418,28 +470,25 @@ impl Container {
     /// Examples:
     ///
     /// ```no_run
-    /// use futures_util::StreamExt;
     /// async {
     ///     use podman_api::Podman;
     ///     use podman_api::opts::ContainerCheckpointOpts;
     ///     let podman = Podman::unix("/run/user/1000/podman/podman.sock");
     ///
     ///     let container = podman.containers().get("79c93f220e3e");
-    ///     let mut export_stream = container.checkpoint_export(
-    ///         &ContainerCheckpointOpts::builder()
-    ///             .print_stats(true)
-    ///             .build(),
-    ///     );
-    ///
-    ///     while let Some(tarball_chunk) = export_stream.next().await {
-    ///         println!("{:?}", tarball_chunk);
+    ///     match container
+    ///         .checkpoint(&ContainerCheckpointOpts::builder().print_stats(true).build())
+    ///         .await
+    ///     {
+    ///         Ok(report) => println!("{} in {:?}", report.id, report.runtime()),
+    ///         Err(e) => eprintln!("{}", e),
     ///     }
     /// };
     /// ```
     pub async fn checkpoint(
         &self,
         opts: &opts::ContainerCheckpointOpts,
-    ) -> Result<Value> {
+    ) -> Result<models::CheckpointReport> {
         let ep = url::construct_ep(
             format!("/libpod/containers/{}/checkpoint", &self.id),
             opts.serialize(),
@@ -532,6 +581,60 @@ impl Container {
             })
     }}
 
+    /// Runs `command` inside this container, waits for it to exit and returns its exit code
+    /// together with the collected stdout and stderr. The container must be running.
+    ///
+    /// Examples:
+    ///
+    /// ```no_run
+    /// async {
+    ///     use podman_api::Podman;
+    ///     use podman_api::opts::ExecOutputOpts;
+    ///     use std::time::Duration;
+    ///     let podman = Podman::unix("/run/user/1000/podman/podman.sock");
+    ///
+    ///     let output = podman
+    ///         .containers()
+    ///         .get("79c93f220e3e")
+    ///         .exec_output(
+    ///             ["sh", "-c", "tr a-z A-Z"],
+    ///             &ExecOutputOpts::builder()
+    ///                 .stdin("hello")
+    ///                 .output_limit(1024 * 1024)
+    ///                 .timeout(Duration::from_secs(10))
+    ///                 .build(),
+    ///         )
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     assert!(output.success());
+    ///     println!("{}", String::from_utf8_lossy(&output.stdout));
+    /// };
+    /// ```
+    pub async fn exec_output(
+        &self,
+        command: impl IntoIterator<Item = impl Into<String>>,
+        opts: &opts::ExecOutputOpts,
+    ) -> Result<models::ExecOutput> {
+        let mut params = opts.params.clone();
+        let command: Vec<String> = command.into_iter().map(Into::into).collect();
+        params.insert("Cmd", command.into());
+        params.insert("AttachStdout", true.into());
+        params.insert("AttachStderr", true.into());
+        params.insert("AttachStdin", opts.stdin.is_some().into());
+
+        let run = async {
+            let exec = self.create_exec(&opts::ExecCreateOpts { params }).await?;
+            exec.output(opts.stdin.as_deref(), opts.output_limit).await
+        };
+        match opts.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, run)
+                .await
+                .unwrap_or(Err(crate::Error::Timeout(timeout))),
+            None => run.await,
+        }
+    }
+
     api_doc! {
     Container => RenameLibpod
     |
@@ -594,7 +697,14 @@ impl Container {
     api_doc! {
     Container => WaitLibpod
     |
-    /// Wait for this container to meet a given condition.
+    /// Wait for this container to meet a given condition and return its exit code. By default
+    /// this waits for the container to stop. When the condition is met without the container
+    /// exiting, for example [`WaitCondition::Healthy`](opts::WaitCondition::Healthy), the
+    /// returned code is `-1`.
+    ///
+    /// The wait is cancelled by dropping the returned future. The configured request timeout
+    /// doesn't apply, use [`ContainerWaitOptsBuilder::timeout`](opts::ContainerWaitOptsBuilder::timeout)
+    /// to limit the time spent waiting.
     ///
     /// Examples:
     ///
@@ -603,32 +713,73 @@ impl Container {
     ///     use podman_api::Podman;
     ///     use podman_api::opts::ContainerWaitOpts;
     ///     use podman_api::models::ContainerStatus;
+    ///     use std::time::Duration;
     ///     let podman = Podman::unix("/run/user/1000/podman/podman.sock");
     ///
-    ///     if let Err(e) = podman
+    ///     match podman
     ///         .containers()
     ///         .get("79c93f220e3e")
     ///         .wait(
     ///             &ContainerWaitOpts::builder()
-    ///                 .conditions([ContainerStatus::Configured])
+    ///                 .conditions([ContainerStatus::Exited])
     ///                 .interval("300ms")
+    ///                 .timeout(Duration::from_secs(60))
     ///                 .build(),
     ///         )
     ///         .await
     ///     {
-    ///         eprintln!("{}", e);
+    ///         Ok(exit_code) => println!("exited with {exit_code}"),
+    ///         Err(e) => eprintln!("{}", e),
     ///     }
     /// };
     /// ```
-    pub async fn wait(&self, opts: &opts::ContainerWaitOpts) -> Result<()> {
+    pub async fn wait(&self, opts: &opts::ContainerWaitOpts) -> Result<i64> {
         let ep = url::construct_ep(
             format!("/libpod/containers/{}/wait", &self.id),
             opts.serialize(),
         );
-        self.podman
-            .post(&ep, Payload::empty(), Headers::none())
-            .await
-            .map(|_| ())
+        let body = self.podman.post_blocking(&ep, opts.timeout).await?;
+        body.trim().trim_matches('"').parse().map_err(|e| {
+            crate::Error::InvalidResponse(format!("invalid container exit code `{body}` - {e}"))
+        })
+    }}
+
+    api_doc! {
+    Container => WaitLibpod
+    |
+    /// Wait for this container to meet a single `condition`, either a
+    /// [`ContainerStatus`](models::ContainerStatus) or a health condition like
+    /// [`WaitCondition::Healthy`](opts::WaitCondition::Healthy). Conditions set in `opts` are
+    /// replaced. See [`Container::wait`](Container::wait) for details.
+    ///
+    /// Examples:
+    ///
+    /// ```no_run
+    /// async {
+    ///     use podman_api::Podman;
+    ///     use podman_api::opts::{ContainerWaitOpts, WaitCondition};
+    ///     use std::time::Duration;
+    ///     let podman = Podman::unix("/run/user/1000/podman/podman.sock");
+    ///
+    ///     let opts = ContainerWaitOpts::builder()
+    ///         .timeout(Duration::from_secs(30))
+    ///         .build();
+    ///     if let Err(e) = podman
+    ///         .containers()
+    ///         .get("79c93f220e3e")
+    ///         .wait_for_condition(WaitCondition::Healthy, &opts)
+    ///         .await
+    ///     {
+    ///         eprintln!("{}", e);
+    ///     }
+    /// };
+    /// ```
+    pub async fn wait_for_condition(
+        &self,
+        condition: impl Into<opts::WaitCondition>,
+        opts: &opts::ContainerWaitOpts,
+    ) -> Result<i64> {
+        self.wait(&opts.with_condition(condition.into())).await
     }}
 
     api_doc! {
@@ -708,6 +859,46 @@ impl Container {
             })
     }}
 
+    /// Attach to this container and return an [`InteractiveSession`](InteractiveSession) wiring
+    /// local stdin, stdout, stderr and terminal resizes to it. Stdin, stdout and stderr are
+    /// attached unless disabled in `opts`. Detach keys set in `opts` are honoured by the
+    /// session as well, by default they are `ctrl-p,ctrl-q`.
+    ///
+    /// Examples:
+    ///
+    /// ```no_run
+    /// async {
+    ///     use podman_api::Podman;
+    ///     let podman = Podman::unix("/run/user/1000/podman/podman.sock");
+    ///     let container = podman.containers().get("79c93f220e3e");
+    ///
+    ///     let session = container.attach_interactive(&Default::default()).await.unwrap();
+    ///     let resize = futures_util::stream::iter([(80, 24)]);
+    ///     let end = session
+    ///         .run(
+    ///             futures_util::io::empty(),
+    ///             futures_util::io::sink(),
+    ///             futures_util::io::sink(),
+    ///             resize,
+    ///         )
+    ///         .await
+    ///         .unwrap();
+    ///     println!("{:?}", end);
+    /// };
+    /// ```
+    pub async fn attach_interactive(
+        &self,
+        opts: &opts::ContainerAttachOpts,
+    ) -> Result<InteractiveSession> {
+        let opts = opts.interactive();
+        let multiplexer = self.attach(&opts).await?;
+        InteractiveSession::for_container(
+            multiplexer,
+            Container::new(self.podman.clone(), self.id.clone()),
+            opts.detach_keys(),
+        )
+    }
+
     api_doc! {
     Container => ChangesLibpod
     |
@@ -1076,7 +1267,7 @@ impl Container {
     }}
 
     api_doc! {
-    Container => Archive
+    Container => ArchiveLibpod
     |
     /// Copy a file/folder from the container.  The resulting stream is a tarball of the extracted
     /// files.
@@ -1112,7 +1303,7 @@ impl Container {
     pub fn copy_from(&self, path: impl AsRef<Path>) -> impl Stream<Item = Result<Vec<u8>>> + '_ {
         self.podman
             .get_stream(format!(
-                "/containers/{}/archive?{}",
+                "/libpod/containers/{}/archive?{}",
                 self.id,
                 url::encoded_pair("path", path.as_ref().to_string_lossy())
             ))
@@ -1120,11 +1311,14 @@ impl Container {
     }}
 
     api_doc! {
-    PutContainer => Archive
+    PutContainer => ArchiveLibpod
     |
     /// Copy a tarball (see `body`) to the container.
     ///
     /// The tarball will be copied to the container and extracted at the given location (see `path`).
+    /// Use [`Container::copy_to_from_stream`](Container::copy_to_from_stream) or
+    /// [`Container::copy_to_from_reader`](Container::copy_to_from_reader) for archives that
+    /// shouldn't be held in memory.
     ///
     /// Examples:
     ///
@@ -1171,7 +1365,7 @@ impl Container {
         self.podman
             .put(
                 &format!(
-                    "/containers/{}/archive?{}",
+                    "/libpod/containers/{}/archive?{}",
                     self.id,
                     url::encoded_pair("path", path.as_ref().to_string_lossy())
                 ),
@@ -1182,7 +1376,153 @@ impl Container {
     }}
 
     api_doc! {
-    PutContainer => Archive
+    PutContainer => ArchiveLibpod
+    |
+    /// Copy a tarball streamed from `stream` to the container and extract it at `path` without
+    /// holding the whole archive in memory. Returns the number of bytes sent.
+    ///
+    /// Examples:
+    ///
+    /// ```no_run
+    /// async {
+    ///     use podman_api::Podman;
+    ///     use futures_util::stream;
+    ///     let podman = Podman::unix("/run/user/1000/podman/podman.sock");
+    ///
+    ///     let chunks = vec![Ok(bytes::Bytes::from_static(b"..."))];
+    ///
+    ///     match podman
+    ///         .containers()
+    ///         .get("fc93f220e3e")
+    ///         .copy_to_from_stream("/tmp", stream::iter(chunks))
+    ///         .await
+    ///     {
+    ///         Ok(sent) => println!("sent {} bytes", sent),
+    ///         Err(e) => eprintln!("{}", e),
+    ///     }
+    /// };
+    /// ```
+    pub async fn copy_to_from_stream<S>(&self, path: impl AsRef<Path>, stream: S) -> Result<u64>
+    where
+        S: Stream<Item = std::io::Result<Bytes>> + Send + 'static,
+    {
+        let sent = Arc::new(AtomicU64::new(0));
+        let counter = Arc::clone(&sent);
+        let stream = stream.inspect_ok(move |chunk| {
+            counter.fetch_add(chunk.len() as u64, Ordering::Relaxed);
+        });
+        self.copy_to(path, Body::wrap_stream(stream)).await?;
+        Ok(sent.load(Ordering::Relaxed))
+    }}
+
+    api_doc! {
+    PutContainer => ArchiveLibpod
+    |
+    /// Copy a tarball read from `reader` to the container and extract it at `path` while it is
+    /// sent to podman. Returns the number of bytes sent.
+    ///
+    /// Examples:
+    ///
+    /// ```no_run
+    /// async {
+    ///     use podman_api::Podman;
+    ///     let podman = Podman::unix("/run/user/1000/podman/podman.sock");
+    ///
+    ///     let archive = futures_util::io::Cursor::new(vec![0, 1]);
+    ///
+    ///     if let Err(e) = podman
+    ///         .containers()
+    ///         .get("fc93f220e3e")
+    ///         .copy_to_from_reader("/tmp", archive)
+    ///         .await
+    ///     {
+    ///         eprintln!("{}", e);
+    ///     }
+    /// };
+    /// ```
+    pub async fn copy_to_from_reader(
+        &self,
+        path: impl AsRef<Path>,
+        reader: impl AsyncRead + Send + 'static,
+    ) -> Result<u64> {
+        self.copy_to_from_stream(path, reader_stream(Box::pin(reader)))
+            .await
+    }}
+
+    api_doc! {
+    PutContainer => ArchiveLibpod
+    |
+    /// Copy a tarball streamed from `stream` to the container like
+    /// [`Container::copy_to_from_stream`](Container::copy_to_from_stream) and report the
+    /// number of bytes sent after every chunk. The last item is marked as finished once podman
+    /// has extracted the archive.
+    ///
+    /// Examples:
+    ///
+    /// ```no_run
+    /// async {
+    ///     use podman_api::Podman;
+    ///     use futures_util::{stream, StreamExt};
+    ///     let podman = Podman::unix("/run/user/1000/podman/podman.sock");
+    ///
+    ///     let chunks = vec![Ok(bytes::Bytes::from_static(b"..."))];
+    ///     let container = podman.containers().get("fc93f220e3e");
+    ///     let mut progress = container.copy_to_with_progress("/tmp", stream::iter(chunks));
+    ///
+    ///     while let Some(progress) = progress.next().await {
+    ///         match progress {
+    ///             Ok(progress) if progress.finished => println!("done"),
+    ///             Ok(progress) => println!("sent {} bytes", progress.bytes_sent),
+    ///             Err(e) => eprintln!("{}", e),
+    ///         }
+    ///     }
+    /// };
+    /// ```
+    pub fn copy_to_with_progress<S>(
+        &self,
+        path: impl AsRef<Path>,
+        stream: S,
+    ) -> impl Stream<Item = Result<models::CopyProgress>> + Unpin + '_
+    where
+        S: Stream<Item = std::io::Result<Bytes>> + Send + 'static,
+    {
+        let path = path.as_ref().to_path_buf();
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut sent = 0;
+        let stream = stream.inspect_ok(move |chunk: &Bytes| {
+            sent += chunk.len() as u64;
+            let _ = tx.send(sent);
+        });
+        let updates = futures_util::stream::unfold(rx, |mut rx| async move {
+            let bytes_sent = rx.recv().await?;
+            let progress = models::CopyProgress {
+                bytes_sent,
+                finished: false,
+            };
+            Some((Ok(progress), rx))
+        });
+        let upload = futures_util::stream::once(async move {
+            let bytes_sent = self.copy_to_from_stream(path, stream).await?;
+            Ok(models::CopyProgress {
+                bytes_sent,
+                finished: true,
+            })
+        });
+        // the upload is polled alongside the updates so that it drives the stream, nothing is
+        // reported after it has finished
+        Box::pin(
+            futures_util::stream::select(updates, upload).scan(false, |done, progress| {
+                if *done {
+                    return futures_util::future::ready(None);
+                }
+                *done = progress.as_ref().map_or(true, |p| p.finished);
+                futures_util::future::ready(Some(progress))
+            }),
+        )
+    }}
+
+    api_doc! {
+    PutContainer => ArchiveLibpod
     |
     /// Copy a byte slice as file into (see `bytes`) the container.
     ///
@@ -1236,6 +1576,226 @@ impl Container {
         self.copy_to(Path::new("/"), data.into()).await.map(|_| ())
     }}
 
+    api_doc! {
+    PutContainer => ArchiveLibpod
+    |
+    /// Copy the file or directory at `host_path` into the container at `container_path` with
+    /// the path semantics of `podman cp`. A directory is copied recursively into
+    /// `container_path` if it is an existing directory, or becomes `container_path` otherwise.
+    /// Only the content of a directory is copied if `host_path` ends with `/.`. The mode,
+    /// modification time and symlinks of the copied files are kept, their ownership only with
+    /// [`preserve_ownership`](opts::ContainerCopyOptsBuilder::preserve_ownership).
+    ///
+    /// The archive is created while it is sent to podman so it is never held in memory.
+    ///
+    /// Examples:
+    ///
+    /// ```no_run
+    /// async {
+    ///     use podman_api::Podman;
+    ///     use podman_api::opts::ContainerCopyOpts;
+    ///     let podman = Podman::unix("/run/user/1000/podman/podman.sock");
+    ///
+    ///     let opts = ContainerCopyOpts::builder().overwrite(true).build();
+    ///     if let Err(e) = podman
+    ///         .containers()
+    ///         .get("fc93f220e3e")
+    ///         .copy_path_to_container("./config", "/etc/app/", &opts)
+    ///         .await
+    ///     {
+    ///         eprintln!("{}", e);
+    ///     }
+    /// };
+    /// ```
+    pub async fn copy_path_to_container(
+        &self,
+        host_path: impl AsRef<Path>,
+        container_path: impl AsRef<Path>,
+        opts: &opts::ContainerCopyOpts,
+    ) -> Result<()> {
+        let host_path = host_path.as_ref();
+        let host_str = host_path.to_string_lossy();
+        let container_path = container_path.as_ref().to_string_lossy();
+
+        let metadata = if opts.follow_symlinks() {
+            std::fs::metadata(host_path)?
+        } else {
+            std::fs::symlink_metadata(host_path)?
+        };
+        let src_is_dir = metadata.is_dir();
+        // paths like `.` have no name of their own
+        let src_name = match host_path.file_name() {
+            Some(name) => name.to_string_lossy().into_owned(),
+            None => host_path
+                .canonicalize()?
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        };
+        let dest_is_dir = match self.stat_path(&*container_path).await {
+            Ok(stat) => Some(stat.is_dir()),
+            Err(e) if e.is_not_found() => None,
+            Err(e) => return Err(e),
+        };
+
+        let (dir, name) =
+            match copy::Destination::resolve(
+                src_is_dir,
+                &container_path,
+                dest_is_dir,
+                opts.overwrite(),
+            )? {
+                copy::Destination::Into if src_is_dir && copy::is_contents_only(&host_str) => {
+                    (container_path.to_string(), ".".to_string())
+                }
+                copy::Destination::Into => (container_path.to_string(), src_name),
+                copy::Destination::As => copy::split_container_path(&container_path),
+            };
+        let body = Body::wrap_stream(copy::pack_path(
+            host_path.to_path_buf(),
+            name,
+            opts.follow_symlinks(),
+        ));
+        let ep = url::construct_ep(
+            format!("/libpod/containers/{}/archive", self.id),
+            Some(url::encoded_pairs([
+                ("path", dir),
+                ("noOverwriteDirNonDir", (!opts.overwrite()).to_string()),
+                ("copyUIDGID", (!opts.preserve_ownership()).to_string()),
+            ])),
+        );
+        self.podman
+            .put(&ep, Payload::XTar(body))
+            .await
+            .map(|_| ())
+    }}
+
+    api_doc! {
+    Container => ArchiveLibpod
+    |
+    /// Copy the file or directory at `container_path` out of the container to `host_path` with
+    /// the path semantics of `podman cp`. A directory is copied recursively into `host_path`
+    /// if it is an existing directory, or becomes `host_path` otherwise. Only the content of a
+    /// directory is copied if `container_path` ends with `/.`. The mode, modification time and
+    /// symlinks of the copied files are kept, their ownership only with
+    /// [`preserve_ownership`](opts::ContainerCopyOptsBuilder::preserve_ownership).
+    ///
+    /// The archive is unpacked while it is received so it is never held in memory.
+    ///
+    /// Examples:
+    ///
+    /// ```no_run
+    /// async {
+    ///     use podman_api::Podman;
+    ///     use podman_api::opts::ContainerCopyOpts;
+    ///     let podman = Podman::unix("/run/user/1000/podman/podman.sock");
+    ///
+    ///     if let Err(e) = podman
+    ///         .containers()
+    ///         .get("fc93f220e3e")
+    ///         .copy_path_from_container("/var/log/app/.", "./logs", &Default::default())
+    ///         .await
+    ///     {
+    ///         eprintln!("{}", e);
+    ///     }
+    /// };
+    /// ```
+    pub async fn copy_path_from_container(
+        &self,
+        container_path: impl AsRef<Path>,
+        host_path: impl AsRef<Path>,
+        opts: &opts::ContainerCopyOpts,
+    ) -> Result<()> {
+        let container_path = container_path.as_ref().to_string_lossy();
+        let host_path = host_path.as_ref();
+        let host_str = host_path.to_string_lossy();
+
+        let (parent, src_name) = copy::split_container_path(&container_path);
+        let mut source = if src_name.is_empty() {
+            parent.clone()
+        } else if parent == "." {
+            src_name.clone()
+        } else {
+            format!("{}/{src_name}", parent.trim_end_matches('/'))
+        };
+        let mut stat = self.stat_path(&source).await?;
+        if opts.follow_symlinks() && stat.is_symlink() {
+            source = if stat.link_target.starts_with('/') {
+                stat.link_target.clone()
+            } else {
+                format!("{}/{}", parent.trim_end_matches('/'), stat.link_target)
+            };
+            stat = self.stat_path(&source).await?;
+        }
+        let src_is_dir = stat.is_dir();
+
+        let dest_is_dir = match std::fs::metadata(host_path) {
+            Ok(metadata) => Some(metadata.is_dir()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+        let target = match copy::Destination::resolve(
+            src_is_dir,
+            &host_str,
+            dest_is_dir,
+            opts.overwrite(),
+        )? {
+            copy::Destination::Into
+                if src_is_dir && (src_name.is_empty() || copy::is_contents_only(&container_path)) =>
+            {
+                host_path.to_path_buf()
+            }
+            copy::Destination::Into => host_path.join(&src_name),
+            copy::Destination::As => host_path.to_path_buf(),
+        };
+
+        let (_, source_name) = copy::split_container_path(&source);
+        let archive = self.copy_from(&source).map_ok(Bytes::from);
+        copy::unpack_path(
+            archive,
+            source_name,
+            target,
+            opts.overwrite(),
+            opts.preserve_ownership(),
+        )
+        .await
+    }}
+
+    api_doc! {
+    Container => ArchiveLibpod
+    |
+    /// Get information about the file or directory at `path` in the container without
+    /// copying it. A symlink is not resolved. Requires podman 3.0 or newer, fails with
+    /// [`Error::UnsupportedByServer`](crate::Error::UnsupportedByServer) otherwise.
+    ///
+    /// Examples:
+    ///
+    /// ```no_run
+    /// async {
+    ///     use podman_api::Podman;
+    ///     let podman = Podman::unix("/run/user/1000/podman/podman.sock");
+    ///
+    ///     match podman.containers().get("fc93f220e3e").stat_path("/etc/hosts").await {
+    ///         Ok(stat) if stat.is_dir() => println!("{} is a directory", stat.name),
+    ///         Ok(stat) => println!("{} has {} bytes", stat.name, stat.size),
+    ///         Err(e) if e.is_not_found() => println!("no such path"),
+    ///         Err(e) => eprintln!("{}", e),
+    ///     }
+    /// };
+    /// ```
+    pub async fn stat_path(&self, path: impl AsRef<Path>) -> Result<models::PathStat> {
+        self.podman.require_api_version((3, 0)).await?;
+        let response = self
+            .podman
+            .head(format!(
+                "/libpod/containers/{}/archive?{}",
+                self.id,
+                url::encoded_pair("path", path.as_ref().to_string_lossy())
+            ))
+            .await?;
+        models::PathStat::try_from(response.headers())
+    }}
+
     api_doc! {
     Container => ResizeLibpod
     |
@@ -1268,6 +1828,53 @@ impl Container {
             .map(|_| ())
     }}
 
+    api_doc! {
+    Container => UpdateLibpod
+    |
+    /// Change the resource limits, restart policy or health check of this container without
+    /// recreating it. Fails with [`Error::UnsupportedByServer`](crate::Error::UnsupportedByServer)
+    /// if the server is too old for any of the options that were set.
+    ///
+    /// Examples:
+    ///
+    /// ```no_run
+    /// async {
+    ///     use podman_api::Podman;
+    ///     use podman_api::models::{LinuxCpu, LinuxPids};
+    ///     use podman_api::opts::{ContainerRestartPolicy, ContainerUpdateOpts};
+    ///     let podman = Podman::unix("/run/user/1000/podman/podman.sock");
+    ///     let container = podman.containers().get("451b27c6b9d3");
+    ///
+    ///     let opts = ContainerUpdateOpts::builder()
+    ///         .cpu(LinuxCpu {
+    ///             cpus: None,
+    ///             mems: None,
+    ///             period: Some(100_000),
+    ///             quota: Some(50_000),
+    ///             realtime_period: None,
+    ///             realtime_runtime: None,
+    ///             shares: None,
+    ///         })
+    ///         .pids(LinuxPids { limit: Some(128) })
+    ///         .restart_policy(ContainerRestartPolicy::OnFailure)
+    ///         .build();
+    ///
+    ///     if let Err(e) = container.update(&opts).await {
+    ///         eprintln!("{}", e);
+    ///     }
+    /// };
+    /// ```
+    pub async fn update(&self, opts: &opts::ContainerUpdateOpts) -> Result<()> {
+        self.podman
+            .require_api_version(opts.required_api_version())
+            .await?;
+        let ep = url::construct_ep(format!("/libpod/containers/{}/update", &self.id), opts.query());
+        self.podman
+            .post(&ep, Payload::Json(opts.body()?), Headers::none())
+            .await
+            .map(|_| ())
+    }}
+
     api_doc! {
     Container => ExportLibpod
     |
@@ -1298,6 +1905,38 @@ impl Container {
         Box::pin(self.podman.get_stream(ep).map_ok(|c| c.to_vec()))
     }}
 
+    api_doc! {
+    Container => ExportLibpod
+    |
+    /// Export the contents of this container as a tarball to the file at `path`, streaming it
+    /// to disk as it is received.
+    ///
+    /// Examples:
+    ///
+    /// ```no_run
+    /// async {
+    ///     use podman_api::Podman;
+    ///     let podman = Podman::unix("/run/user/1000/podman/podman.sock");
+    ///
+    ///     match podman
+    ///         .containers()
+    ///         .get("79c93f220e3e")
+    ///         .export_to_path("/tmp/79c93f220e3e.tar")
+    ///         .await
+    ///     {
+    ///         Ok(exported) => println!("{} {}", exported.size, exported.digest),
+    ///         Err(e) => eprintln!("{}", e),
+    ///     }
+    /// };
+    /// ```
+    pub async fn export_to_path(
+        &self,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<models::ExportedArchive> {
+        let ep = format!("/libpod/containers/{}/export", &self.id);
+        crate::body::write_to_path(self.podman.get_stream(ep), path.as_ref(), false).await
+    }}
+
     api_doc! {
     Container => RestoreLibpod
     |
@@ -1311,12 +1950,15 @@ impl Container {
     ///     let podman = Podman::unix("/run/user/1000/podman/podman.sock");
     ///
     ///     match podman.containers().get("79c93f220e3e").restore(&Default::default()).await {
-    ///         Ok(info) => println!("{info:?}"),
+    ///         Ok(report) => println!("{} in {:?}", report.id, report.runtime()),
     ///         Err(e) =>  eprintln!("{e}"),
     ///     }
     /// };
     /// ```
-    pub async fn restore(&self, opts: &opts::ContainerRestoreOpts) -> Result<Value> {
+    pub async fn restore(
+        &self,
+        opts: &opts::ContainerRestoreOpts,
+    ) -> Result<models::RestoreReport> {
         let ep = url::construct_ep(
             format!("/libpod/containers/{}/restore", &self.id),
             opts.serialize(),
@@ -1333,6 +1975,9 @@ impl Containers {
     |
     /// Create a container with specified options.
     ///
+    /// Fails with [`Error::UnsupportedByServer`](crate::Error::UnsupportedByServer) if the opts
+    /// contain fields the server is too old to understand.
+    ///
     /// Examples:
     ///
     /// ```no_run
@@ -1364,6 +2009,9 @@ impl Containers {
         &self,
         opts: &opts::ContainerCreateOpts,
     ) -> Result<models::ContainerCreateCreatedBody> {
+        if let Some(required) = opts.required_api_version() {
+            self.podman.require_api_version(required).await?;
+        }
         self.podman
             .post_json(
                 &"/libpod/containers/create",
@@ -1491,6 +2139,80 @@ impl Containers {
         self.podman.get_json("/libpod/containers/showmounted").await
     }}
 
+    api_doc! {
+    Container => CreateLibpod
+    |
+    /// Run a container like `podman run`. The image is pulled according to the pull policy,
+    /// then the container is created and started. Use the returned
+    /// [`ContainerRun`](ContainerRun) to follow the output of the container and collect its exit
+    /// status.
+    ///
+    /// Examples:
+    ///
+    /// ```no_run
+    /// async {
+    ///     use podman_api::Podman;
+    ///     use podman_api::opts::{ContainerCreateOpts, ContainerRunOpts, PullPolicy};
+    ///     let podman = Podman::unix("/run/user/1000/podman/podman.sock");
+    ///
+    ///     let opts = ContainerRunOpts::builder(
+    ///         ContainerCreateOpts::builder()
+    ///             .image("alpine")
+    ///             .command(["echo", "hello"])
+    ///             .build(),
+    ///     )
+    ///     .pull_policy(PullPolicy::Missing)
+    ///     .remove(true)
+    ///     .build();
+    ///
+    ///     let exit_code = match podman.containers().run(&opts).await {
+    ///         Ok(run) => run.output(futures_util::io::sink(), futures_util::io::sink()).await,
+    ///         Err(e) => Err(e),
+    ///     };
+    ///
+    ///     match exit_code {
+    ///         Ok(exit_code) => println!("exited with {exit_code}"),
+    ///         Err(e) => eprintln!("{}", e),
+    ///     }
+    /// };
+    /// ```
+    pub async fn run(&self, opts: &opts::ContainerRunOpts) -> Result<ContainerRun> {
+        if let Some(image) = opts.image()
+            && opts.pull_policy != opts::PullPolicy::Never
+        {
+            let mut pull = opts::PullOpts::builder()
+                .reference(image)
+                .policy(opts.pull_policy.clone())
+                .quiet(true);
+            if let Some(auth) = &opts.auth {
+                pull.auth(auth.clone());
+            }
+            self.podman
+                .images()
+                .pull(&pull.build())
+                .try_for_each(|_| async { Ok(()) })
+                .await?;
+        }
+
+        let created = self.create(&opts.create_opts()).await?;
+        let run = ContainerRun {
+            container: self.get(created.id),
+            remove: opts.remove && !opts.detach,
+            logs_opts: opts::ContainerLogsOpts::builder()
+                .stdout(true)
+                .stderr(true)
+                .follow(true)
+                .build(),
+        };
+        if let Err(e) = run.container.start(None).await {
+            if run.remove {
+                let _ = run.container.remove().await;
+            }
+            return Err(e);
+        }
+        Ok(run)
+    }}
+
     api_doc! {
     Container => PruneLibpod
     |
@@ -1518,4 +2240,122 @@ impl Containers {
             .post_json(&ep, Payload::empty(), Headers::none())
             .await
     }}
+
+    api_doc! {
+    Container => RestoreLibpod
+    |
+    /// Restore a new container from a checkpoint tar.gz exported with
+    /// [`Container::checkpoint_to_path`](Container::checkpoint_to_path) or
+    /// [`Container::checkpoint_export`](Container::checkpoint_export). The archive is read from
+    /// `reader` while it is sent to podman, the id of the new container is in the report.
+    ///
+    /// Requires podman 4.0 or newer, fails with
+    /// [`Error::UnsupportedByServer`](crate::Error::UnsupportedByServer) otherwise.
+    ///
+    /// Examples:
+    ///
+    /// ```no_run
+    /// async {
+    ///     use podman_api::Podman;
+    ///     use podman_api::opts::ContainerRestoreOpts;
+    ///     let podman = Podman::unix("/run/user/1000/podman/podman.sock");
+    ///
+    ///     let archive = std::fs::read("/tmp/79c93f220e3e.tar.gz").unwrap();
+    ///     let opts = ContainerRestoreOpts::builder()
+    ///         .name("migrated")
+    ///         .print_stats(true)
+    ///         .build();
+    ///
+    ///     match podman
+    ///         .containers()
+    ///         .restore_from_archive(futures_util::io::Cursor::new(archive), &opts)
+    ///         .await
+    ///     {
+    ///         Ok(report) => println!("restored {} in {:?}", report.id, report.runtime()),
+    ///         Err(e) => eprintln!("{}", e),
+    ///     }
+    /// };
+    /// ```
+    pub async fn restore_from_archive(
+        &self,
+        reader: impl AsyncRead + Send + 'static,
+        opts: &opts::ContainerRestoreOpts,
+    ) -> Result<models::RestoreReport> {
+        self.podman.require_api_version((4, 0)).await?;
+        // podman ignores the container in the path when importing
+        let ep = url::construct_ep(
+            "/libpod/containers/import/restore",
+            opts.for_import().serialize(),
+        );
+        let body = Body::wrap_stream(reader_stream(Box::pin(reader)));
+        self.podman
+            .post_json(&ep, Payload::XTar(body), Headers::none())
+            .await
+    }}
+}
+
+#[derive(Debug)]
+/// A container started with [`Containers::run`](Containers::run).
+///
+/// In the foreground, which is the default, the container is removed by
+/// [`wait`](ContainerRun::wait) or [`output`](ContainerRun::output) when
+/// [`remove`](opts::ContainerRunOptsBuilder::remove) is set. A detached container is removed by
+/// podman once it exits.
+pub struct ContainerRun {
+    container: Container,
+    remove: bool,
+    logs_opts: opts::ContainerLogsOpts,
+}
+
+impl ContainerRun {
+    /// The started container.
+    pub fn container(&self) -> &Container {
+        &self.container
+    }
+
+    /// Follow the stdout and stderr of the container from its start until it exits.
+    pub fn logs(&self) -> impl Stream<Item = Result<tty::TtyChunk>> + '_ {
+        self.container.logs(&self.logs_opts)
+    }
+
+    /// Wait for the container to exit and return its exit code.
+    pub async fn wait(self) -> Result<i64> {
+        let exit_code = self
+            .container
+            .wait(&opts::ContainerWaitOpts::default())
+            .await;
+        if self.remove {
+            let removed = self.container.remove().await;
+            if exit_code.is_ok() {
+                removed?;
+            }
+        }
+        exit_code
+    }
+
+    /// Write the output of the container to `stdout` and `stderr` until it exits and return its
+    /// exit code. With a TTY all output is written to `stdout`.
+    pub async fn output<O, E>(self, mut stdout: O, mut stderr: E) -> Result<i64>
+    where
+        O: AsyncWrite + Unpin,
+        E: AsyncWrite + Unpin,
+    {
+        let copied = async {
+            let mut logs = self.logs();
+            while let Some(chunk) = logs.next().await {
+                match chunk? {
+                    tty::TtyChunk::StdOut(bytes) => stdout.write_all(&bytes).await?,
+                    tty::TtyChunk::StdErr(bytes) => stderr.write_all(&bytes).await?,
+                    tty::TtyChunk::StdIn(_) => {}
+                }
+            }
+            stdout.flush().await?;
+            stderr.flush().await?;
+            Ok::<_, Error>(())
+        }
+        .await;
+        let exit_code = self.wait().await;
+        copied?;
+        exit_code
+    }
 }
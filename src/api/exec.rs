@@ -1,9 +1,12 @@
 use crate::{
+    api::InteractiveSession,
     conn::{tty, Headers, Payload},
-    opts, Result, Value,
+    models, opts, Error, Result,
 };
 
 use containers_api::url;
+use futures_util::{AsyncWriteExt, StreamExt};
+use std::time::Duration;
 
 #[derive(Debug)]
 /// [Api Reference](https://docs.podman.io/en/latest/_static/api.html?version=v4.3.1#tag/Exec)
@@ -135,6 +138,59 @@ impl Exec {
         }
     }}
 
+    /// Starts this exec instance attached and returns an
+    /// [`InteractiveSession`](InteractiveSession) wiring local stdin, stdout, stderr and
+    /// terminal resizes to it. The exec instance should be created with stdin, stdout and stderr
+    /// attached. The session detaches on `ctrl-p,ctrl-q` unless changed with
+    /// [`InteractiveSession::detach_keys`](InteractiveSession::detach_keys).
+    ///
+    /// Examples:
+    ///
+    /// ```no_run
+    /// async {
+    ///     use podman_api::Podman;
+    ///     let podman = Podman::unix("/run/user/1000/podman/podman.sock");
+    ///     let container = podman.containers().get("451b27c6b9d3");
+    ///
+    ///     let exec = container
+    ///         .create_exec(
+    ///             &podman_api::opts::ExecCreateOpts::builder()
+    ///                 .command(["bash"])
+    ///                 .tty(true)
+    ///                 .attach_stdin(true)
+    ///                 .attach_stdout(true)
+    ///                 .attach_stderr(true)
+    ///                 .build(),
+    ///         )
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let session = exec.start_interactive(&Default::default()).await.unwrap();
+    ///     let end = session
+    ///         .run(
+    ///             futures_util::io::empty(),
+    ///             futures_util::io::sink(),
+    ///             futures_util::io::sink(),
+    ///             futures_util::stream::iter([(80, 24)]),
+    ///         )
+    ///         .await
+    ///         .unwrap();
+    ///     println!("{:?}", end);
+    /// };
+    /// ```
+    pub async fn start_interactive(
+        &self,
+        opts: &opts::ExecStartOpts,
+    ) -> Result<InteractiveSession> {
+        let multiplexer = self.start(opts).await?.ok_or_else(|| {
+            Error::StringError("cannot start an interactive session detached".into())
+        })?;
+        InteractiveSession::for_exec(
+            multiplexer,
+            Exec::new_tty(self.podman.clone(), self.id.clone()),
+        )
+    }
+
     api_doc! {
     Exec => InspectLibpod
     |
@@ -164,7 +220,7 @@ impl Exec {
     ///     }
     /// };
     /// ```
-    pub async fn inspect(&self) -> Result<Value> {
+    pub async fn inspect(&self) -> Result<models::InspectExecSession> {
         let ep = format!("/libpod/exec/{}/json", &self.id);
         self.podman.get_json(&ep).await
     }}
@@ -208,4 +264,100 @@ impl Exec {
         );
         self.podman.post(&ep, Payload::None::<&str>, Headers::none()).await.map(|_| ())
     }}
+
+    /// Starts this exec instance, writes `stdin` to it and collects its output until the
+    /// command exits.
+    pub(crate) async fn output(
+        &self,
+        stdin: Option<&[u8]>,
+        output_limit: Option<usize>,
+    ) -> Result<models::ExecOutput> {
+        let opts = opts::ExecStartOpts::builder().tty(self.is_tty).build();
+        let (mut reader, mut writer) = self
+            .start(&opts)
+            .await?
+            .ok_or_else(|| Error::InvalidResponse("exec session was not attached".into()))?
+            .split();
+
+        let write = async {
+            if let Some(stdin) = stdin {
+                writer.write_all(stdin).await?;
+                writer.close().await?;
+            }
+            Ok::<_, Error>(())
+        };
+
+        let read = async {
+            let mut output = models::ExecOutput::default();
+            let mut remaining = output_limit.unwrap_or(usize::MAX);
+            while let Some(chunk) = reader.next().await {
+                let (buf, bytes) = match chunk? {
+                    tty::TtyChunk::StdOut(bytes) => (&mut output.stdout, bytes),
+                    tty::TtyChunk::StdErr(bytes) => (&mut output.stderr, bytes),
+                    tty::TtyChunk::StdIn(_) => continue,
+                };
+                let n = bytes.len().min(remaining);
+                buf.extend_from_slice(&bytes[..n]);
+                remaining -= n;
+                output.truncated |= n < bytes.len();
+            }
+            Ok::<_, Error>(output)
+        };
+
+        let ((), mut output) = futures_util::future::try_join(write, read).await?;
+        output.exit_code = self.wait().await?;
+        Ok(output)
+    }
+
+    /// Waits until the process of this exec instance exits and returns its exit code. The state
+    /// of the exec instance is polled with an interval growing from 50ms up to 1s.
+    ///
+    /// An exec instance that was created but never started fails with
+    /// [`Error::ExecNotStarted`](crate::Error::ExecNotStarted). Podman removes exec instances 5
+    /// minutes after they exit, after that this fails with
+    /// [`Error::NotFound`](crate::Error::NotFound).
+    ///
+    /// Examples:
+    ///
+    /// ```no_run
+    /// async {
+    ///     use podman_api::Podman;
+    ///     let podman = Podman::unix("/run/user/1000/podman/podman.sock");
+    ///     let container = podman.containers().get("451b27c6b9d3");
+    ///
+    ///     let exec = container
+    ///         .create_exec(
+    ///             &podman_api::opts::ExecCreateOpts::builder()
+    ///                 .command(["sleep", "10"])
+    ///                 .build(),
+    ///         )
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let opts = podman_api::opts::ExecStartOpts::builder().detach(true).build();
+    ///     exec.start(&opts).await.unwrap();
+    ///
+    ///     match exec.wait().await {
+    ///         Ok(exit_code) => println!("exited with {exit_code}"),
+    ///         Err(e) => eprintln!("{}", e)
+    ///     }
+    /// };
+    /// ```
+    pub async fn wait(&self) -> Result<i64> {
+        let mut interval = Duration::from_millis(50);
+        loop {
+            let info = self.inspect().await?;
+            if !info.running.unwrap_or_default() {
+                // podman reports exit code 0 without a process for sessions never started
+                if info.pid == Some(0) {
+                    return Err(Error::ExecNotStarted(self.id.to_string()));
+                }
+                return info
+                    .exit_code
+                    .ok_or_else(|| Error::InvalidResponse("exec exit code missing".into()));
+            }
+            tokio::time::sleep(interval).await;
+            interval = (interval * 2).min(Duration::from_secs(1));
+        }
+    }
 }
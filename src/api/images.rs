@@ -1,10 +1,15 @@
 use crate::{
     api::ApiResource,
-    conn::{Headers, Payload},
+    body::{file_stream, reader_stream, write_to_path},
+    conn::{hyper::Body, Headers, Payload},
     models, opts, Error, Result, Stream, TryStreamExt,
 };
 
-use containers_api::{tarball, url};
+use bytes::Bytes;
+use futures_util::{AsyncRead, StreamExt, TryFutureExt};
+use std::path::Path;
+
+use containers_api::url;
 
 impl_api_ty!(
     Image => id
@@ -238,6 +243,47 @@ impl Image {
         Box::pin(self.podman.get_stream(ep).map_ok(|c| c.to_vec()))
     }}
 
+    api_doc! {
+    Image => GetLibpod
+    |
+    /// Export this image to the file at `path`, streaming it to disk as it is received. Images
+    /// exported with a directory format like
+    /// [`ImageExportFormat::OciDir`](crate::opts::ImageExportFormat::OciDir) are unpacked into
+    /// the directory at `path`. The returned digest can be checked with
+    /// [`ExportedArchive::verify`](crate::models::ExportedArchive::verify).
+    ///
+    /// Examples:
+    ///
+    /// ```no_run
+    /// async {
+    ///     use podman_api::opts::{ImageExportFormat, ImageExportOpts};
+    ///     use podman_api::Podman;
+    ///     let podman = Podman::unix("/run/user/1000/podman/podman.sock");
+    ///
+    ///     let opts = ImageExportOpts::builder()
+    ///         .format(ImageExportFormat::OciArchive)
+    ///         .build();
+    ///
+    ///     match podman
+    ///         .images()
+    ///         .get("alpine")
+    ///         .export_to_path(&opts, "/tmp/alpine.tar")
+    ///         .await
+    ///     {
+    ///         Ok(exported) => println!("{} {}", exported.size, exported.digest),
+    ///         Err(e) => eprintln!("{}", e),
+    ///     }
+    /// };
+    /// ```
+    pub async fn export_to_path(
+        &self,
+        opts: &opts::ImageExportOpts,
+        path: impl AsRef<Path>,
+    ) -> Result<models::ExportedArchive> {
+        let ep = url::construct_ep(format!("/libpod/images/{}/get", &self.id), opts.serialize());
+        write_to_path(self.podman.get_stream(ep), path.as_ref(), opts.exports_dir()).await
+    }}
+
     api_doc! {
     Image => ChangesLibpod
     |
@@ -306,7 +352,8 @@ impl Image {
     api_doc! {
     Image => PushLibpod
     |
-    /// Push this image to a container registry.
+    /// Push this image to a container registry. Errors reported by podman while pushing are
+    /// returned as [`Error::ServerError`](crate::Error::ServerError).
     ///
     /// Examples:
     ///
@@ -342,9 +389,8 @@ impl Image {
     /// };
     /// ```
     pub fn push(&self, opts: &opts::ImagePushOpts) -> impl Stream<Item = Result<String>> + Unpin + '_ {
-        let headers = opts
-            .auth_header()
-            .map(|a| Headers::single(crate::conn::AUTH_HEADER, a));
+        let explicit = opts.auth_header();
+        let reference = opts.destination().unwrap_or(self.id.as_ref()).to_owned();
 
         let ep = url::construct_ep(
             format!("/libpod/images/{}/push", &self.id),
@@ -352,21 +398,78 @@ impl Image {
         );
 
         let reader = Box::pin(
-            self.podman
-                .post_stream(
-                    ep,
-                    Payload::empty(),
-                    headers,
-                )
-                .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e)),
+            async move {
+                let headers = self.podman.registry_auth(explicit, Some(&reference)).await?;
+                Ok::<_, Error>(self.podman.post_stream(ep, Payload::empty(), headers))
+            }
+            .try_flatten_stream()
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e)),
         )
         .into_async_read();
 
         Box::pin(
             futures_codec::FramedRead::new(reader, futures_codec::LinesCodec)
                 .map_err(Error::IO)
+                .and_then(|line: String| async move {
+                    match serde_json::from_str::<models::ImagePushReport>(&line) {
+                        Ok(models::ImagePushReport { error: Some(error), .. }) => {
+                            Err(Error::ServerError(models::JsonError::new(error)))
+                        }
+                        _ => Ok(line),
+                    }
+                }),
         )
     }}
+
+    api_doc! {
+    Image => PushLibpod
+    |
+    /// Push this image like [`Image::push`](Image::push) and return the aggregated progress of
+    /// the push after every update reported by podman.
+    ///
+    /// Examples:
+    ///
+    /// ```no_run
+    /// async {
+    ///     use podman_api::opts::ImagePushOpts;
+    ///     use podman_api::Podman;
+    ///     use futures_util::StreamExt;
+    ///
+    ///     let podman = Podman::unix("/run/user/1000/podman/podman.sock");
+    ///
+    ///     let image = podman.images().get("alpine");
+    ///     let mut progress = image.push_progress(
+    ///         &ImagePushOpts::builder()
+    ///             .destination("my-registry/alpine")
+    ///             .build(),
+    ///     );
+    ///
+    ///     while let Some(progress) = progress.next().await {
+    ///         match progress {
+    ///             Ok(progress) if progress.finished => {
+    ///                 println!("pushed {:?}", progress.manifest_digest)
+    ///             }
+    ///             Ok(progress) => println!("{:?}", progress.status),
+    ///             Err(e) => eprintln!("{e}"),
+    ///         }
+    ///     }
+    /// };
+    /// ```
+    pub fn push_progress(
+        &self,
+        opts: &opts::ImagePushOpts,
+    ) -> impl Stream<Item = Result<models::TransferProgress>> + Unpin + '_ {
+        let mut progress = models::TransferProgress::default();
+        self.push(opts).map_ok(move |line| {
+            // older versions of podman report plain text lines
+            let report = serde_json::from_str(&line).unwrap_or(models::ImagePushReport {
+                stream: Some(line),
+                ..Default::default()
+            });
+            progress.update_push(&report);
+            progress.clone()
+        })
+    }}
 }
 
 impl Images {
@@ -405,33 +508,137 @@ impl Images {
         &self,
         opts: &opts::ImageBuildOpts,
     ) -> Result<impl Stream<Item = Result<models::ImageBuildLibpod200Response>> + Unpin + '_> {
-        let mut bytes = Vec::default();
         let path = opts
             .get_param("path")
             .ok_or_else(|| Error::OptsSerialization("expected a path to build context".into()))?;
-        tarball::dir(&mut bytes, path)?;
+        Ok(self.build_with_context(opts::BuildContext::dir(path), opts))
+    }}
 
-        let ep = url::construct_ep("/libpod/build", opts.serialize());
-        let reader = Box::pin(
-            self.podman
-                .post_stream(ep, Payload::Tar(bytes), Headers::none())
-                .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e)),
-        )
-        .into_async_read();
+    api_doc! {
+    Image => BuildLibpod
+    |
+    /// Build an image like [`Images::build`](Images::build) and return the progress as typed
+    /// [`BuildEvent`](models::BuildEvent)s. A failed build yields a
+    /// [`BuildEvent::Error`](models::BuildEvent::Error).
+    ///
+    /// Examples:
+    ///
+    /// ```no_run
+    /// async {
+    ///     use podman_api::Podman;
+    ///     use podman_api::models::BuildEvent;
+    ///     use futures_util::StreamExt;
+    ///     use podman_api::opts::ImageBuildOpts;
+    ///
+    ///     let podman = Podman::unix("/run/user/1000/podman/podman.sock");
+    ///
+    ///     let opts = ImageBuildOpts::builder("/path/to/context")
+    ///             .tag("myimage:1.0.0")
+    ///             .build();
+    ///
+    ///     let images = podman.images();
+    ///     match images.build_events(&opts) {
+    ///         Ok(mut events) => while let Some(event) = events.next().await {
+    ///             match event {
+    ///                 Ok(BuildEvent::Step { index, total, instruction }) => {
+    ///                     println!("[{index}/{total}] {instruction}")
+    ///                 }
+    ///                 Ok(BuildEvent::ImageId(id)) => println!("built {id}"),
+    ///                 Ok(_) => {}
+    ///                 Err(e) => eprintln!("{}", e),
+    ///             }
+    ///         },
+    ///         Err(e) => eprintln!("{}", e),
+    ///     };
+    /// };
+    /// ```
+    pub fn build_events(
+        &self,
+        opts: &opts::ImageBuildOpts,
+    ) -> Result<impl Stream<Item = Result<models::BuildEvent>> + Unpin + '_> {
+        Ok(typed_build_events(self.build(opts)?))
+    }}
 
-        Ok(Box::pin(
-            futures_codec::FramedRead::new(reader, futures_codec::LinesCodec)
-                .map_err(Error::IO)
-                .and_then(|s: String| async move {
-                    match serde_json::from_str(&s) {
-                        Ok(s) => Ok(s),
-                        Err(e) => match serde_json::from_str::<models::JsonError>(&s) {
-                            Ok(e) => Err(Error::ServerError(e)),
-                            Err(_) => Err(e.into())
-                        }
-                    }
-                }),
-        ))
+    api_doc! {
+    Image => BuildLibpod
+    |
+    /// Build an image like [`Images::build`](Images::build), wait for the build to finish and
+    /// return the built image.
+    ///
+    /// Examples:
+    ///
+    /// ```no_run
+    /// async {
+    ///     use podman_api::Podman;
+    ///     use podman_api::opts::ImageBuildOpts;
+    ///
+    ///     let podman = Podman::unix("/run/user/1000/podman/podman.sock");
+    ///
+    ///     let opts = ImageBuildOpts::builder("/path/to/context")
+    ///             .tag("myimage:1.0.0")
+    ///             .build();
+    ///
+    ///     match podman.images().build_and_wait(&opts).await {
+    ///         Ok(image) => println!("built {}", image.id()),
+    ///         Err(e) => eprintln!("{}", e),
+    ///     }
+    /// };
+    /// ```
+    pub async fn build_and_wait(&self, opts: &opts::ImageBuildOpts) -> Result<Image> {
+        let mut events = self.build_events(opts)?;
+        let mut image_id = None;
+        while let Some(event) = events.try_next().await? {
+            match event {
+                models::BuildEvent::ImageId(id) => image_id = Some(id),
+                models::BuildEvent::Error(e) => return Err(Error::ServerError(e)),
+                _ => {}
+            }
+        }
+        image_id
+            .map(|id| self.get(id))
+            .ok_or_else(|| Error::InvalidResponse("build finished without an image id".into()))
+    }}
+
+    api_doc! {
+    Image => BuildLibpod
+    |
+    /// Build an image from the given build context. The context is streamed to podman as the
+    /// build goes, the `path` of `opts` is ignored so they can be created with
+    /// [`ImageBuildOpts::context_builder`](opts::ImageBuildOpts::context_builder).
+    ///
+    /// Examples:
+    ///
+    /// ```no_run
+    /// async {
+    ///     use podman_api::Podman;
+    ///     use futures_util::StreamExt;
+    ///     use podman_api::opts::{BuildContext, ImageBuildOpts};
+    ///
+    ///     let podman = Podman::unix("/run/user/1000/podman/podman.sock");
+    ///
+    ///     let context = BuildContext::builder()
+    ///         .dockerfile("FROM alpine\nCOPY app.sh /app.sh\nCMD /app.sh")
+    ///         .file_with_mode("app.sh", "#!/bin/sh\necho hello", 0o755)
+    ///         .build()
+    ///         .unwrap();
+    ///     let opts = ImageBuildOpts::context_builder().tag("myimage:1.0.0").build();
+    ///
+    ///     let images = podman.images();
+    ///     let mut build_stream = images.build_with_context(context, &opts);
+    ///     while let Some(chunk) = build_stream.next().await {
+    ///         match chunk {
+    ///             Ok(chunk) => println!("{:?}", chunk),
+    ///             Err(e) => eprintln!("{}", e),
+    ///         }
+    ///     }
+    /// };
+    /// ```
+    pub fn build_with_context(
+        &self,
+        context: opts::BuildContext,
+        opts: &opts::ImageBuildOpts,
+    ) -> impl Stream<Item = Result<models::ImageBuildLibpod200Response>> + Unpin + '_ {
+        build_stream(&self.podman, context, opts)
     }}
 
     api_doc! {
@@ -473,14 +680,15 @@ impl Images {
     api_doc! {
     Image => PullLibpod
     |
-    /// Pull one or more images from a container registry.
+    /// Pull one or more images from a container registry. Errors reported by podman while
+    /// pulling are returned as [`Error::ServerError`](crate::Error::ServerError).
     ///
     /// Examples:
     ///
     /// ```no_run
     /// async {
-    ///     use futures_util::{StreamExt, TryStreamExt};
-    ///     use podman_api::{Error, Podman};
+    ///     use futures_util::TryStreamExt;
+    ///     use podman_api::Podman;
     ///     use podman_api::opts::PullOpts;
     ///     let podman = Podman::unix("/run/user/1000/podman/podman.sock");
     ///
@@ -491,12 +699,6 @@ impl Images {
     ///                 .reference("docker.io/library/alpine")
     ///                 .build(),
     ///             )
-    ///             .map(|report| {
-    ///                 report.and_then(|report| match report.error {
-    ///                     Some(error) => Err(Error::InvalidResponse(error)),
-    ///                     None => Ok(report),
-    ///                 })
-    ///             })
     ///             .try_collect::<Vec<_>>()
     ///             .await;
     ///
@@ -510,15 +712,18 @@ impl Images {
         opts: &opts::PullOpts,
     ) -> impl Stream<Item = Result<models::LibpodImagesPullReport>> + Unpin + '_ {
         let ep = url::construct_ep("/libpod/images/pull", opts.serialize());
+        let explicit = opts.auth_header();
+        let reference = opts.reference_without_credentials().map(str::to_owned);
         let reader = Box::pin(
-            self.podman
-                .post_stream(
-                    ep,
-                    Payload::empty(),
-                    opts.auth_header()
-                        .map(|a| Headers::single(crate::conn::AUTH_HEADER, a)),
-                )
-                .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e)),
+            async move {
+                let headers = self
+                    .podman
+                    .registry_auth(explicit, reference.as_deref())
+                    .await?;
+                Ok::<_, Error>(self.podman.post_stream(ep, Payload::empty(), headers))
+            }
+            .try_flatten_stream()
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e)),
         )
         .into_async_read();
 
@@ -526,11 +731,61 @@ impl Images {
             futures_codec::FramedRead::new(reader, futures_codec::LinesCodec)
                 .map_err(Error::IO)
                 .and_then(|s: String| async move {
-                    serde_json::from_str(&s).map_err(Error::SerdeJsonError)
+                    let report: models::LibpodImagesPullReport = serde_json::from_str(&s)?;
+                    match report.error {
+                        Some(error) => Err(Error::ServerError(models::JsonError::new(error))),
+                        None => Ok(report),
+                    }
                 }),
         )
     }}
 
+    api_doc! {
+    Image => PullLibpod
+    |
+    /// Pull one or more images like [`Images::pull`](Images::pull) and return the aggregated
+    /// progress of the pull after every update reported by podman.
+    ///
+    /// Examples:
+    ///
+    /// ```no_run
+    /// async {
+    ///     use futures_util::StreamExt;
+    ///     use podman_api::Podman;
+    ///     use podman_api::opts::PullOpts;
+    ///     let podman = Podman::unix("/run/user/1000/podman/podman.sock");
+    ///
+    ///     let images = podman.images();
+    ///     let mut progress = images.pull_progress(
+    ///         &PullOpts::builder()
+    ///             .reference("docker.io/library/alpine")
+    ///             .build(),
+    ///     );
+    ///
+    ///     while let Some(progress) = progress.next().await {
+    ///         match progress {
+    ///             Ok(progress) => println!(
+    ///                 "{:.0}% - {} of {} layers",
+    ///                 progress.fraction() * 100.0,
+    ///                 progress.layers_finished(),
+    ///                 progress.layers.len()
+    ///             ),
+    ///             Err(e) => eprintln!("{}", e),
+    ///         }
+    ///     }
+    /// };
+    /// ```
+    pub fn pull_progress(
+        &self,
+        opts: &opts::PullOpts,
+    ) -> impl Stream<Item = Result<models::TransferProgress>> + Unpin + '_ {
+        let mut progress = models::TransferProgress::default();
+        self.pull(opts).map_ok(move |report| {
+            progress.update_pull(&report);
+            progress.clone()
+        })
+    }}
+
     api_doc! {
     Image => LoadLibpod
     |
@@ -562,6 +817,96 @@ impl Images {
             .await
     }}
 
+    api_doc! {
+    Image => LoadLibpod
+    |
+    /// Load an image (oci-archive or docker-archive) streamed from `stream` without holding the
+    /// whole archive in memory.
+    ///
+    /// Examples:
+    ///
+    /// ```no_run
+    /// async {
+    ///     use podman_api::Podman;
+    ///     use futures_util::stream;
+    ///     let podman = Podman::unix("/run/user/1000/podman/podman.sock");
+    ///
+    ///     let chunks = vec![Ok(bytes::Bytes::from_static(b"..."))];
+    ///
+    ///     match podman.images().load_from_stream(stream::iter(chunks)).await {
+    ///         Ok(info) => println!("{:?}", info),
+    ///         Err(e) => eprintln!("{}", e),
+    ///     }
+    /// };
+    /// ```
+    pub async fn load_from_stream<S>(&self, stream: S) -> Result<models::ImageLoadReport>
+    where
+        S: Stream<Item = std::io::Result<Bytes>> + Send + 'static,
+    {
+        self.podman
+            .post_json(
+                "/libpod/images/load",
+                Payload::XTar(Body::wrap_stream(stream)),
+                Headers::none(),
+            )
+            .await
+    }}
+
+    api_doc! {
+    Image => LoadLibpod
+    |
+    /// Load an image (oci-archive or docker-archive) read from `reader` while it is sent to
+    /// podman.
+    ///
+    /// Examples:
+    ///
+    /// ```no_run
+    /// async {
+    ///     use podman_api::Podman;
+    ///     let podman = Podman::unix("/run/user/1000/podman/podman.sock");
+    ///
+    ///     let archive = futures_util::io::Cursor::new(vec![0, 1]);
+    ///
+    ///     match podman.images().load_from_reader(archive).await {
+    ///         Ok(info) => println!("{:?}", info),
+    ///         Err(e) => eprintln!("{}", e),
+    ///     }
+    /// };
+    /// ```
+    pub async fn load_from_reader(
+        &self,
+        reader: impl AsyncRead + Send + 'static,
+    ) -> Result<models::ImageLoadReport> {
+        self.load_from_stream(reader_stream(Box::pin(reader))).await
+    }}
+
+    api_doc! {
+    Image => LoadLibpod
+    |
+    /// Load an image (oci-archive or docker-archive) from the archive at `path`. The file is
+    /// streamed to podman as it is read.
+    ///
+    /// Examples:
+    ///
+    /// ```no_run
+    /// async {
+    ///     use podman_api::Podman;
+    ///     let podman = Podman::unix("/run/user/1000/podman/podman.sock");
+    ///
+    ///     match podman.images().load_from_path("image_archive.tar").await {
+    ///         Ok(info) => println!("{:?}", info),
+    ///         Err(e) => eprintln!("{}", e),
+    ///     }
+    /// };
+    /// ```
+    pub async fn load_from_path(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Result<models::ImageLoadReport> {
+        let file = std::fs::File::open(path)?;
+        self.load_from_stream(file_stream(file)).await
+    }}
+
     api_doc! {
     Image => ImportLibpod
     |
@@ -606,6 +951,124 @@ impl Images {
             .await
     }}
 
+    api_doc! {
+    Image => ImportLibpod
+    |
+    /// Import a previously exported tarball as an image, streamed from `stream` without holding
+    /// the whole tarball in memory.
+    ///
+    /// Examples:
+    ///
+    /// ```no_run
+    /// async {
+    ///     use podman_api::Podman;
+    ///     use podman_api::opts::ImageImportOpts;
+    ///     use futures_util::stream;
+    ///     let podman = Podman::unix("/run/user/1000/podman/podman.sock");
+    ///
+    ///     let chunks = vec![Ok(bytes::Bytes::from_static(b"..."))];
+    ///
+    ///     if let Err(e) = podman
+    ///         .images()
+    ///         .import_from_stream(
+    ///             &ImageImportOpts::builder()
+    ///                 .reference("rockylinux/rockylinux:8")
+    ///                 .build(),
+    ///             stream::iter(chunks),
+    ///         )
+    ///         .await
+    ///     {
+    ///         eprintln!("{}", e);
+    ///     }
+    /// };
+    /// ```
+    pub async fn import_from_stream<S>(
+        &self,
+        opts: &opts::ImageImportOpts,
+        stream: S,
+    ) -> Result<models::LibpodImagesPullReport>
+    where
+        S: Stream<Item = std::io::Result<Bytes>> + Send + 'static,
+    {
+        self.podman
+            .post_json(
+                url::construct_ep("/libpod/images/import", opts.serialize()),
+                Payload::XTar(Body::wrap_stream(stream)),
+                Headers::none(),
+            )
+            .await
+    }}
+
+    api_doc! {
+    Image => ImportLibpod
+    |
+    /// Import a previously exported tarball as an image, read from `reader` while it is sent to
+    /// podman.
+    ///
+    /// Examples:
+    ///
+    /// ```no_run
+    /// async {
+    ///     use podman_api::Podman;
+    ///     use podman_api::opts::ImageImportOpts;
+    ///     let podman = Podman::unix("/run/user/1000/podman/podman.sock");
+    ///
+    ///     let tarball = futures_util::io::Cursor::new(vec![0, 1]);
+    ///
+    ///     if let Err(e) = podman
+    ///         .images()
+    ///         .import_from_reader(&ImageImportOpts::builder().build(), tarball)
+    ///         .await
+    ///     {
+    ///         eprintln!("{}", e);
+    ///     }
+    /// };
+    /// ```
+    pub async fn import_from_reader(
+        &self,
+        opts: &opts::ImageImportOpts,
+        reader: impl AsyncRead + Send + 'static,
+    ) -> Result<models::LibpodImagesPullReport> {
+        self.import_from_stream(opts, reader_stream(Box::pin(reader)))
+            .await
+    }}
+
+    api_doc! {
+    Image => ImportLibpod
+    |
+    /// Import the tarball at `path` as an image. The file is streamed to podman as it is read.
+    ///
+    /// Examples:
+    ///
+    /// ```no_run
+    /// async {
+    ///     use podman_api::Podman;
+    ///     use podman_api::opts::ImageImportOpts;
+    ///     let podman = Podman::unix("/run/user/1000/podman/podman.sock");
+    ///
+    ///     if let Err(e) = podman
+    ///         .images()
+    ///         .import_from_path(
+    ///             &ImageImportOpts::builder()
+    ///                 .reference("rockylinux/rockylinux:8")
+    ///                 .build(),
+    ///             "rootfs.tar",
+    ///         )
+    ///         .await
+    ///     {
+    ///         eprintln!("{}", e);
+    ///     }
+    /// };
+    /// ```
+    pub async fn import_from_path(
+        &self,
+        opts: &opts::ImageImportOpts,
+        path: impl AsRef<Path>,
+    ) -> Result<models::LibpodImagesPullReport> {
+        let file = std::fs::File::open(path)?;
+        self.import_from_stream(opts, file_stream(file)).await
+    }}
+
     api_doc! {
     Image => DeleteAllLibpod
     |
@@ -703,7 +1166,13 @@ impl Images {
         opts: &opts::ImageSearchOpts,
     ) -> Result<Vec<models::RegistrySearchResponse>> {
         let ep = url::construct_ep("/libpod/images/search", opts.serialize());
-        self.podman.get_json(&ep).await
+        let headers = self
+            .podman
+            .registry_auth(None, opts.registry_term())
+            .await?;
+        self.podman
+            .get_json_with_headers(&ep, headers)
+            .await
     }}
 
     api_doc! {
@@ -737,4 +1206,99 @@ impl Images {
         let ep = url::construct_ep("/libpod/images/export", opts.serialize());
         Box::pin(self.podman.get_stream(ep).map_ok(|c| c.to_vec()))
     }}
+
+    api_doc! {
+    Image => ExportLibpod
+    |
+    /// Export multiple images to the file at `path`, streaming them to disk as they are
+    /// received.
+    ///
+    /// Examples:
+    ///
+    /// ```no_run
+    /// async {
+    ///     use podman_api::opts::ImagesExportOpts;
+    ///     use podman_api::Podman;
+    ///     let podman = Podman::unix("/run/user/1000/podman/podman.sock");
+    ///
+    ///     let opts = ImagesExportOpts::builder()
+    ///         .references(["alpine", "busybox"])
+    ///         .build();
+    ///
+    ///     match podman.images().export_to_path(&opts, "/tmp/images.tar").await {
+    ///         Ok(exported) => println!("{} {}", exported.size, exported.digest),
+    ///         Err(e) => eprintln!("{}", e),
+    ///     }
+    /// };
+    /// ```
+    pub async fn export_to_path(
+        &self,
+        opts: &opts::ImagesExportOpts,
+        path: impl AsRef<Path>,
+    ) -> Result<models::ExportedArchive> {
+        let ep = url::construct_ep("/libpod/images/export", opts.serialize());
+        write_to_path(self.podman.get_stream(ep), path.as_ref(), opts.exports_dir()).await
+    }}
+}
+
+/// Streams the output of a build of `context`, the stream doesn't borrow `opts`.
+pub(crate) fn build_stream<'podman>(
+    podman: &'podman crate::Podman,
+    context: opts::BuildContext,
+    opts: &opts::ImageBuildOpts,
+) -> impl Stream<Item = Result<models::ImageBuildLibpod200Response>> + Unpin + use<'podman> {
+    let ep = url::construct_ep("/libpod/build", opts.serialize());
+    let body = context.into_body(opts.ignore_file(), opts.dockerfiles());
+    let reader = Box::pin(
+        async move {
+            let headers = podman.registry_config().await?;
+            Ok::<_, Error>(podman.post_stream(ep, Payload::Tar(body), headers))
+        }
+            .try_flatten_stream()
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e)),
+    )
+    .into_async_read();
+
+    Box::pin(
+        futures_codec::FramedRead::new(reader, futures_codec::LinesCodec)
+            .map_err(Error::IO)
+            .and_then(|s: String| async move {
+                match serde_json::from_str(&s) {
+                    Ok(s) => Ok(s),
+                    Err(e) => match serde_json::from_str::<models::JsonError>(&s) {
+                        Ok(e) => Err(Error::ServerError(e)),
+                        Err(_) => Err(e.into()),
+                    },
+                }
+            }),
+    )
+}
+
+/// Parses the output of a build into typed events, a failed build yields a
+/// [`BuildEvent::Error`](models::BuildEvent::Error).
+pub(crate) fn typed_build_events<'a>(
+    responses: impl Stream<Item = Result<models::ImageBuildLibpod200Response>> + Send + 'a,
+) -> impl Stream<Item = Result<models::BuildEvent>> + Unpin + Send + 'a {
+    Box::pin(
+        futures_util::stream::unfold(
+            (Box::pin(responses), models::BuildEventParser::default(), false),
+            |(mut responses, mut parser, finished)| async move {
+                if finished {
+                    return None;
+                }
+                let events = match responses.next().await {
+                    Some(Ok(response)) => Ok(parser.parse(&response)),
+                    Some(Err(Error::ServerError(e))) => Ok(parser.fail(e)),
+                    Some(Err(e)) => Err(e),
+                    None => {
+                        let events = parser.finish().into_iter().collect();
+                        return Some((Ok(events), (responses, parser, true)));
+                    }
+                };
+                Some((events, (responses, parser, false)))
+            },
+        )
+        .map_ok(|events| futures_util::stream::iter(events.into_iter().map(Ok)))
+        .try_flatten(),
+    )
 }
@@ -1,10 +1,15 @@
 use crate::{
-    api::ApiResource,
+    api::{build_stream, typed_build_events, ApiResource},
     conn::{Headers, Payload},
-    models, opts, Result,
+    models, opts, Error, Podman, Result, Stream, TryStreamExt,
 };
 
 use containers_api::url;
+use futures_util::{
+    stream::{self, BoxStream},
+    StreamExt, TryFutureExt,
+};
+use std::collections::VecDeque;
 
 impl_api_ty!(
     Manifest => name
@@ -99,7 +104,8 @@ impl Manifest {
     api_doc! {
     Manifest => DeleteLibpod
     |
-    /// Remove an image digest from this manifest list.
+    /// Remove an image digest from this manifest list. Requires podman 4.0 or newer, fails with
+    /// [`Error::UnsupportedByServer`](crate::Error::UnsupportedByServer) otherwise.
     ///
     /// Examples:
     ///
@@ -119,6 +125,7 @@ impl Manifest {
     /// };
     /// ```
     pub async fn remove_image(&self, digest: impl Into<String>) -> Result<models::ManifestRemoveReport> {
+        self.podman.require_api_version((4, 0)).await?;
         let ep = url::construct_ep(
             format!("/libpod/manifests/{}", &self.name),
             Some(url::encoded_pair("digest", digest.into())),
@@ -155,9 +162,11 @@ impl Manifest {
             format!("/libpod/manifests/{}/push", &self.name),
             opts.serialize(),
         );
-        self.podman
-            .post_json(&ep, Payload::empty(), Headers::none())
-            .await
+        let headers = self
+            .podman
+            .registry_auth(None, Some(opts.destination().as_str()))
+            .await?;
+        self.podman.post_json(&ep, Payload::empty(), headers).await
     }}
 
     api_doc! {
@@ -188,7 +197,8 @@ impl Manifests {
     api_doc! {
     Manifest => CreateLibpod
     |
-    /// Create a manifest list.
+    /// Create a manifest list. Requires podman 4.0 or newer, fails with
+    /// [`Error::UnsupportedByServer`](crate::Error::UnsupportedByServer) otherwise.
     ///
     /// Examples:
     ///
@@ -213,10 +223,240 @@ impl Manifests {
     /// };
     /// ```
     pub async fn create(&self, opts: &opts::ManifestCreateOpts) -> Result<Manifest> {
+        self.podman.require_api_version((4, 0)).await?;
         let ep = url::construct_ep(format!("/libpod/manifests/{}", opts.name()), opts.serialize());
         self.podman
             .post_json(&ep, Payload::empty(), Headers::none())
             .await
             .map(|resp: models::IdResponse| self.podman.manifests().get(resp.id))
     }}
+
+    api_doc! {
+    Manifest => CreateLibpod
+    |
+    /// Build an image from `context` for each of the platforms of `opts` and collect them in a
+    /// manifest list annotated with their os, architecture and variant. The progress of each
+    /// platform is reported as it happens, the list is created once all builds have finished
+    /// and pushed last if `opts` ask for it. A failed build yields its
+    /// [`BuildEvent::Error`](models::BuildEvent::Error) before the stream ends with the error.
+    ///
+    /// Examples:
+    ///
+    /// ```no_run
+    /// async {
+    ///     use podman_api::Podman;
+    ///     use podman_api::models::ManifestBuildEvent;
+    ///     use futures_util::StreamExt;
+    ///     use podman_api::opts::{BuildContext, ManifestBuildOpts, ManifestPushOpts, Platform};
+    ///
+    ///     let podman = Podman::unix("/run/user/1000/podman/podman.sock");
+    ///
+    ///     let opts = ManifestBuildOpts::builder("myimage:1.0.0")
+    ///         .platforms([
+    ///             Platform::new("linux").arch("amd64"),
+    ///             Platform::new("linux").arch("arm").version("v7"),
+    ///         ])
+    ///         .parallel(true)
+    ///         .push(ManifestPushOpts::builder("quay.io/user/myimage:1.0.0").all(true).build())
+    ///         .build();
+    ///
+    ///     let manifests = podman.manifests();
+    ///     let mut events = manifests.build(BuildContext::dir("/path/to/context"), &opts);
+    ///     while let Some(event) = events.next().await {
+    ///         match event {
+    ///             Ok(ManifestBuildEvent::Build { platform, event }) => {
+    ///                 println!("[{platform}] {event:?}")
+    ///             }
+    ///             Ok(ManifestBuildEvent::Pushed { digest, .. }) => println!("pushed {digest}"),
+    ///             Ok(_) => {}
+    ///             Err(e) => eprintln!("{}", e),
+    ///         }
+    ///     }
+    /// };
+    /// ```
+    pub fn build(
+        &self,
+        context: opts::BuildContext,
+        opts: &opts::ManifestBuildOpts,
+    ) -> impl Stream<Item = Result<models::ManifestBuildEvent>> + Unpin + '_ {
+        let opts = opts.clone();
+        let podman = &self.podman;
+        Box::pin(
+            async move {
+                let contexts = context.replicate(opts.platforms().len()).await?;
+                let builds = opts
+                    .platforms()
+                    .iter()
+                    .zip(contexts)
+                    .enumerate()
+                    .map(|(index, (platform, context))| {
+                        (index, platform.clone(), opts.build_opts(platform), context)
+                    })
+                    .collect::<Vec<_>>();
+                let platform_build = move |(index, platform, build_opts, context): (
+                    usize,
+                    opts::Platform,
+                    opts::ImageBuildOpts,
+                    opts::BuildContext,
+                )| {
+                    typed_build_events(build_stream(podman, context, &build_opts))
+                        .map_ok(move |event| (index, platform.clone(), event))
+                };
+                // sequential builds are only started once the previous one has finished
+                let builds = if opts.is_parallel() {
+                    stream::select_all(builds.into_iter().map(platform_build)).boxed()
+                } else {
+                    stream::iter(builds).map(platform_build).flatten().boxed()
+                };
+                let state = BuildState {
+                    images: vec![None; opts.platforms().len()],
+                    builds: Some(builds),
+                    failed: None,
+                    added: VecDeque::new(),
+                    manifest: None,
+                    pushed: false,
+                    opts,
+                };
+                Ok::<_, Error>(stream::try_unfold(state, move |state| {
+                    next_build_event(podman, state)
+                }))
+            }
+            .try_flatten_stream(),
+        )
+    }}
+
+    api_doc! {
+    Manifest => CreateLibpod
+    |
+    /// Build a manifest list like [`Manifests::build`](Manifests::build), wait for the builds to
+    /// finish and return the created manifest list.
+    ///
+    /// Examples:
+    ///
+    /// ```no_run
+    /// async {
+    ///     use podman_api::Podman;
+    ///     use podman_api::opts::{BuildContext, ManifestBuildOpts, Platform};
+    ///
+    ///     let podman = Podman::unix("/run/user/1000/podman/podman.sock");
+    ///
+    ///     let opts = ManifestBuildOpts::builder("myimage:1.0.0")
+    ///         .platforms([
+    ///             Platform::new("linux").arch("amd64"),
+    ///             Platform::new("linux").arch("arm64"),
+    ///         ])
+    ///         .build();
+    ///
+    ///     let context = BuildContext::dir("/path/to/context");
+    ///     match podman.manifests().build_and_wait(context, &opts).await {
+    ///         Ok(manifest) => println!("created {}", manifest.name()),
+    ///         Err(e) => eprintln!("{}", e),
+    ///     }
+    /// };
+    /// ```
+    pub async fn build_and_wait(
+        &self,
+        context: opts::BuildContext,
+        opts: &opts::ManifestBuildOpts,
+    ) -> Result<Manifest> {
+        let mut events = self.build(context, opts);
+        let mut manifest = None;
+        while let Some(event) = events.try_next().await? {
+            if let models::ManifestBuildEvent::Created(id) = event {
+                manifest = Some(self.get(id));
+            }
+        }
+        manifest.ok_or_else(|| {
+            Error::InvalidResponse("build finished without a manifest list".into())
+        })
+    }}
+}
+
+/// State of a multi-platform build between the events it yields.
+struct BuildState<'podman> {
+    builds: Option<BoxStream<'podman, Result<(usize, opts::Platform, models::BuildEvent)>>>,
+    /// Ids of the built images by the index of their platform.
+    images: Vec<Option<String>>,
+    failed: Option<models::JsonError>,
+    /// Built images which are yet to be added to the manifest list.
+    added: VecDeque<(opts::Platform, String)>,
+    manifest: Option<Manifest>,
+    pushed: bool,
+    opts: opts::ManifestBuildOpts,
+}
+
+/// Advances a multi-platform build by a single event: the events of the builds, then the
+/// creation of the manifest list, the addition of each image and the push of the list.
+async fn next_build_event<'podman>(
+    podman: &'podman Podman,
+    mut state: BuildState<'podman>,
+) -> Result<Option<(models::ManifestBuildEvent, BuildState<'podman>)>> {
+    if let Some(e) = state.failed.take() {
+        return Err(Error::ServerError(e));
+    }
+
+    if let Some(builds) = &mut state.builds {
+        if let Some((index, platform, event)) = builds.try_next().await? {
+            let event = match event {
+                models::BuildEvent::ImageId(image_id) => {
+                    state.images[index] = Some(image_id.clone());
+                    models::ManifestBuildEvent::Built { platform, image_id }
+                }
+                event => {
+                    if let models::BuildEvent::Error(e) = &event {
+                        state.failed = Some(e.clone());
+                    }
+                    models::ManifestBuildEvent::Build { platform, event }
+                }
+            };
+            return Ok(Some((event, state)));
+        }
+        state.builds = None;
+        for (platform, image) in state.opts.platforms().iter().zip(&mut state.images) {
+            let image_id = image.take().ok_or_else(|| {
+                Error::InvalidResponse(format!("build for {platform} finished without an image id"))
+            })?;
+            state.added.push_back((platform.clone(), image_id));
+        }
+    }
+
+    let manifest = match &state.manifest {
+        Some(manifest) => manifest,
+        None => {
+            let manifest = podman.manifests().create(&state.opts.create_opts()).await?;
+            let id = manifest.name().to_string();
+            state.manifest = Some(manifest);
+            return Ok(Some((models::ManifestBuildEvent::Created(id), state)));
+        }
+    };
+
+    if let Some((platform, image_id)) = state.added.pop_front() {
+        let mut add_opts = opts::ManifestImageAddOpts::builder()
+            .images([format!("containers-storage:{image_id}")])
+            .os(platform.os_name());
+        if let Some(arch) = platform.arch_name() {
+            add_opts = add_opts.arch(arch);
+        }
+        if let Some(variant) = platform.variant_name() {
+            add_opts = add_opts.variant(variant);
+        }
+        manifest.add_image(&add_opts.build()).await?;
+        return Ok(Some((
+            models::ManifestBuildEvent::Added { platform, image_id },
+            state,
+        )));
+    }
+
+    match state.opts.push_opts() {
+        Some(push_opts) if !state.pushed => {
+            let pushed = manifest.push(push_opts).await?;
+            let event = models::ManifestBuildEvent::Pushed {
+                destination: push_opts.destination().to_string(),
+                digest: pushed.id,
+            };
+            state.pushed = true;
+            Ok(Some((event, state)))
+        }
+        _ => Ok(None),
+    }
 }
@@ -7,6 +7,7 @@ mod manifests;
 mod networks;
 mod pods;
 mod secrets;
+mod session;
 mod volumes;
 
 pub use containers::*;
@@ -16,6 +17,7 @@ pub use manifests::*;
 pub use networks::*;
 pub use pods::*;
 pub use secrets::*;
+pub use session::*;
 pub use volumes::*;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -33,6 +35,26 @@ pub enum ApiResource {
     System,
 }
 
+impl ApiResource {
+    /// Matches the first segment of an endpoint path like `containers` in
+    /// `/libpod/containers/{id}/json`.
+    pub(crate) fn from_endpoint_segment(segment: &str) -> Option<Self> {
+        use ApiResource::*;
+        match segment {
+            "containers" => Some(Containers),
+            "exec" => Some(Exec),
+            "images" => Some(Images),
+            "manifests" => Some(Manifests),
+            "networks" => Some(Networks),
+            "pods" => Some(Pods),
+            "secrets" => Some(Secrets),
+            "volumes" => Some(Volumes),
+            "system" => Some(System),
+            _ => None,
+        }
+    }
+}
+
 impl AsRef<str> for ApiResource {
     fn as_ref(&self) -> &str {
         use ApiResource::*;
@@ -0,0 +1,265 @@
+use crate::{
+    api::{Container, Exec},
+    conn::tty,
+    Error, Result,
+};
+
+use futures_util::{
+    future::{self, Either},
+    stream::{Stream, StreamExt},
+    AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt,
+};
+
+/// Detach keys used by podman when none are specified.
+const DEFAULT_DETACH_KEYS: &str = "ctrl-p,ctrl-q";
+
+#[derive(Debug)]
+enum ResizeTarget {
+    Container(Container),
+    Exec(Exec),
+}
+
+impl ResizeTarget {
+    async fn resize(&self, width: usize, height: usize) -> Result<()> {
+        match self {
+            ResizeTarget::Container(container) => container.resize(width, height).await,
+            ResizeTarget::Exec(exec) => exec.resize(width, height).await,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The reason an [`InteractiveSession`](InteractiveSession) has finished.
+pub enum SessionEnd {
+    /// The process closed its output, usually because it exited.
+    Exited,
+    /// The detach key sequence was read from stdin.
+    Detached,
+}
+
+/// An interactive session with a container or an exec instance that connects local input and
+/// outputs to the process and forwards terminal size changes.
+///
+/// Created with [`Container::attach_interactive`](crate::api::Container::attach_interactive) or
+/// [`Exec::start_interactive`](crate::api::Exec::start_interactive).
+pub struct InteractiveSession {
+    multiplexer: tty::Multiplexer,
+    target: ResizeTarget,
+    detach_keys: DetachKeys,
+}
+
+impl std::fmt::Debug for InteractiveSession {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InteractiveSession")
+            .field("target", &self.target)
+            .field("detach_keys", &self.detach_keys)
+            .finish()
+    }
+}
+
+impl InteractiveSession {
+    pub(crate) fn for_container(
+        multiplexer: tty::Multiplexer,
+        container: Container,
+        detach_keys: Option<&str>,
+    ) -> Result<Self> {
+        Ok(Self {
+            multiplexer,
+            target: ResizeTarget::Container(container),
+            detach_keys: DetachKeys::parse(detach_keys.unwrap_or(DEFAULT_DETACH_KEYS))?,
+        })
+    }
+
+    pub(crate) fn for_exec(multiplexer: tty::Multiplexer, exec: Exec) -> Result<Self> {
+        Ok(Self {
+            multiplexer,
+            target: ResizeTarget::Exec(exec),
+            detach_keys: DetachKeys::parse(DEFAULT_DETACH_KEYS)?,
+        })
+    }
+
+    /// Sets the key sequence that detaches from the session when read from stdin, for example
+    /// `ctrl-p,ctrl-q`. Each key is either a single character or `ctrl-<value>` where `<value>`
+    /// is one of `a-z`, `@`, `^`, `[`, `\`, `]` or `_`. An empty string disables detaching.
+    pub fn detach_keys(mut self, keys: impl AsRef<str>) -> Result<Self> {
+        self.detach_keys = DetachKeys::parse(keys.as_ref())?;
+        Ok(self)
+    }
+
+    /// Runs the session until the process closes its output or the detach keys are read.
+    ///
+    /// Bytes read from `stdin` are written to the process, once `stdin` reaches EOF the input of
+    /// the process is closed. Output of the process is written to `stdout` and `stderr`, with a
+    /// TTY all output is written to `stdout`. Each `(width, height)` item of `resize` resizes the
+    /// terminal of the process.
+    pub async fn run<I, O, E, R>(
+        self,
+        stdin: I,
+        stdout: O,
+        stderr: E,
+        resize: R,
+    ) -> Result<SessionEnd>
+    where
+        I: AsyncRead + Unpin,
+        O: AsyncWrite + Unpin,
+        E: AsyncWrite + Unpin,
+        R: Stream<Item = (usize, usize)> + Unpin,
+    {
+        let InteractiveSession {
+            multiplexer,
+            target,
+            mut detach_keys,
+        } = self;
+        let (mut reader, mut writer) = multiplexer.split();
+
+        let output = Box::pin(async move {
+            let (mut stdout, mut stderr) = (stdout, stderr);
+            while let Some(chunk) = reader.next().await {
+                match chunk? {
+                    tty::TtyChunk::StdOut(bytes) => {
+                        stdout.write_all(&bytes).await?;
+                        stdout.flush().await?;
+                    }
+                    tty::TtyChunk::StdErr(bytes) => {
+                        stderr.write_all(&bytes).await?;
+                        stderr.flush().await?;
+                    }
+                    tty::TtyChunk::StdIn(_) => {}
+                }
+            }
+            Ok::<_, Error>(SessionEnd::Exited)
+        });
+
+        let input = Box::pin(async move {
+            let mut stdin = stdin;
+            let mut buf = vec![0; 4096];
+            let mut forward = Vec::with_capacity(buf.len());
+            loop {
+                let n = stdin.read(&mut buf).await?;
+                if n == 0 {
+                    writer.close().await?;
+                    // keep the session alive until the process exits
+                    return future::pending().await;
+                }
+                forward.clear();
+                let detached = detach_keys.scan(&buf[..n], &mut forward);
+                writer.write_all(&forward).await?;
+                writer.flush().await?;
+                if detached {
+                    return Ok::<_, Error>(SessionEnd::Detached);
+                }
+            }
+        });
+
+        let resize = Box::pin(async move {
+            let mut resize = resize;
+            while let Some((width, height)) = resize.next().await {
+                target.resize(width, height).await?;
+            }
+            future::pending::<Result<SessionEnd>>().await
+        });
+
+        match future::select(output, future::select(input, resize)).await {
+            Either::Left((end, _)) => end,
+            Either::Right((Either::Left((end, _)), _)) => end,
+            Either::Right((Either::Right((end, _)), _)) => end,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+/// Detects the detach key sequence in a stream of input bytes.
+struct DetachKeys {
+    keys: Vec<u8>,
+    matched: usize,
+}
+
+impl DetachKeys {
+    fn parse(keys: &str) -> Result<Self> {
+        let keys = keys
+            .split(',')
+            .filter(|key| !key.is_empty())
+            .map(|key| {
+                let invalid = || Error::StringError(format!("invalid detach key `{key}`"));
+                match key.strip_prefix("ctrl-") {
+                    Some(ctrl) => match ctrl.as_bytes() {
+                        [c @ b'a'..=b'z'] => Ok(c - b'a' + 1),
+                        [b'@'] => Ok(0),
+                        [c @ (b'[' | b'\\' | b']' | b'^' | b'_')] => Ok(c - b'@'),
+                        _ => Err(invalid()),
+                    },
+                    None => match key.as_bytes() {
+                        [c] => Ok(*c),
+                        _ => Err(invalid()),
+                    },
+                }
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { keys, matched: 0 })
+    }
+
+    /// Copies `input` to `out` holding back bytes that may be a part of the detach sequence.
+    /// Returns `true` once the whole sequence was read, the remaining input is discarded.
+    fn scan(&mut self, input: &[u8], out: &mut Vec<u8>) -> bool {
+        if self.keys.is_empty() {
+            out.extend_from_slice(input);
+            return false;
+        }
+        for &byte in input {
+            if byte != self.keys[self.matched] && self.matched > 0 {
+                out.extend_from_slice(&self.keys[..self.matched]);
+                self.matched = 0;
+            }
+            if byte == self.keys[self.matched] {
+                self.matched += 1;
+                if self.matched == self.keys.len() {
+                    return true;
+                }
+            } else {
+                out.push(byte);
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DetachKeys;
+
+    #[test]
+    fn parses_detach_keys() {
+        assert_eq!(
+            DetachKeys::parse("ctrl-p,ctrl-q").unwrap().keys,
+            vec![16, 17]
+        );
+        assert_eq!(
+            DetachKeys::parse("ctrl-@,ctrl-[,ctrl-_,x").unwrap().keys,
+            vec![0, 27, 31, b'x']
+        );
+        assert!(DetachKeys::parse("").unwrap().keys.is_empty());
+        assert!(DetachKeys::parse("ctrl-1").is_err());
+        assert!(DetachKeys::parse("ab").is_err());
+    }
+
+    #[test]
+    fn detects_detach_sequence() {
+        let mut keys = DetachKeys::parse("ctrl-p,ctrl-q").unwrap();
+        let mut out = vec![];
+        assert!(!keys.scan(b"ls\x10", &mut out));
+        assert_eq!(out, b"ls");
+        // a partial sequence followed by other input is forwarded
+        assert!(!keys.scan(b"\x10a", &mut out));
+        assert_eq!(out, b"ls\x10\x10a");
+
+        out.clear();
+        assert!(!keys.scan(b"x\x10", &mut out));
+        assert!(keys.scan(b"\x11ignored", &mut out));
+        assert_eq!(out, b"x");
+
+        let mut keys = DetachKeys::parse("").unwrap();
+        out.clear();
+        assert!(!keys.scan(b"\x10\x11", &mut out));
+        assert_eq!(out, b"\x10\x11");
+    }
+}
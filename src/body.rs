@@ -0,0 +1,246 @@
+//! Request bodies streamed to podman without holding them in memory.
+
+use crate::models::ExportedArchive;
+
+use bytes::{Buf, Bytes};
+use futures_util::{stream, AsyncRead, AsyncReadExt, Stream, StreamExt};
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    io::{self, BufWriter, Read, Write},
+    path::Path,
+    pin::Pin,
+};
+use tokio::sync::{mpsc, oneshot};
+
+/// Size of the chunks in which bodies are sent to podman.
+pub(crate) const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Streams everything `write` writes to the given writer. The writer runs on a separate thread
+/// as the stream is consumed so that blocking IO doesn't stall the runtime.
+pub(crate) fn blocking_stream<F>(write: F) -> impl Stream<Item = io::Result<Bytes>>
+where
+    F: FnOnce(BufWriter<ChannelWriter>) -> io::Result<()> + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel(4);
+    std::thread::spawn(move || {
+        let writer = BufWriter::with_capacity(CHUNK_SIZE, ChannelWriter(tx.clone()));
+        if let Err(e) = write(writer) {
+            let _ = tx.blocking_send(Err(e));
+        }
+    });
+    stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    })
+}
+
+/// Streams the content of `file`.
+pub(crate) fn file_stream(mut file: fs::File) -> impl Stream<Item = io::Result<Bytes>> {
+    blocking_stream(move |mut writer| {
+        io::copy(&mut file, &mut writer)?;
+        writer.flush()
+    })
+}
+
+/// Streams everything read from `reader`.
+pub(crate) fn reader_stream(
+    reader: Pin<Box<dyn AsyncRead + Send>>,
+) -> impl Stream<Item = io::Result<Bytes>> {
+    stream::try_unfold(reader, |mut reader| async move {
+        let mut buf = vec![0; CHUNK_SIZE];
+        let n = reader.read(&mut buf).await?;
+        if n == 0 {
+            return Ok(None);
+        }
+        buf.truncate(n);
+        Ok(Some((Bytes::from(buf), reader)))
+    })
+}
+
+/// Sends everything written to it as chunks over a channel.
+pub(crate) struct ChannelWriter(mpsc::Sender<io::Result<Bytes>>);
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .blocking_send(Ok(Bytes::copy_from_slice(buf)))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "request was dropped"))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Writes `stream` to the file at `path`, or unpacks it as a tar archive into the directory at
+/// `path` when `unpack` is set. The file is written on a separate thread while the stream is
+/// consumed.
+pub(crate) async fn write_to_path<S>(
+    stream: S,
+    path: &Path,
+    unpack: bool,
+) -> crate::Result<ExportedArchive>
+where
+    S: Stream<Item = crate::Result<Bytes>>,
+{
+    let mut hasher = Sha256::new();
+    let mut size = 0;
+    let dest = path.to_path_buf();
+    read_blocking(
+        stream,
+        |chunk| {
+            hasher.update(chunk);
+            size += chunk.len() as u64;
+        },
+        move |reader| {
+            let result = if unpack {
+                fs::create_dir_all(&dest).and_then(|_| {
+                    tar::Archive::new(&mut *reader).unpack(&dest)?;
+                    // consume the padding after the end of the archive
+                    io::copy(reader, &mut io::sink()).map(|_| ())
+                })
+            } else {
+                fs::File::create(&dest).and_then(|mut file| {
+                    io::copy(reader, &mut file)?;
+                    file.sync_all()
+                })
+            };
+            if result.is_err() && !unpack {
+                let _ = fs::remove_file(&dest);
+            }
+            result
+        },
+    )
+    .await?;
+    Ok(ExportedArchive {
+        path: path.to_path_buf(),
+        size,
+        digest: format!("sha256:{:x}", hasher.finalize()),
+    })
+}
+
+/// Passes the content of `stream` to `read` which runs on a separate thread so that blocking IO
+/// doesn't stall the runtime. `on_chunk` sees every chunk before it is read. An error of the
+/// stream is returned rather than the result of `read`.
+pub(crate) async fn read_blocking<S, F, T>(
+    stream: S,
+    mut on_chunk: impl FnMut(&Bytes),
+    read: F,
+) -> crate::Result<T>
+where
+    S: Stream<Item = crate::Result<Bytes>>,
+    F: FnOnce(&mut dyn Read) -> io::Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    let (tx, rx) = mpsc::channel(4);
+    let (result_tx, result_rx) = oneshot::channel();
+    std::thread::spawn(move || {
+        let mut reader = ChannelReader {
+            rx,
+            chunk: Bytes::new(),
+        };
+        let _ = result_tx.send(read(&mut reader));
+    });
+
+    let mut stream = Box::pin(stream);
+    let mut failed = None;
+    while let Some(chunk) = stream.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                let _ = tx
+                    .send(Err(io::Error::other("failed to receive the stream")))
+                    .await;
+                failed = Some(e);
+                break;
+            }
+        };
+        on_chunk(&chunk);
+        // the reader only stops early if it failed, its error is returned below
+        if tx.send(Ok(chunk)).await.is_err() {
+            break;
+        }
+    }
+    drop(tx);
+
+    let read = result_rx
+        .await
+        .unwrap_or_else(|_| Err(io::Error::other("reader thread panicked")));
+    if let Some(e) = failed {
+        return Err(e);
+    }
+    Ok(read?)
+}
+
+/// Reads the chunks received over a channel, an error received ends the data with that error.
+struct ChannelReader {
+    rx: mpsc::Receiver<io::Result<Bytes>>,
+    chunk: Bytes,
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.chunk.is_empty() {
+            match self.rx.blocking_recv() {
+                Some(chunk) => self.chunk = chunk?,
+                None => return Ok(0),
+            }
+        }
+        let n = buf.len().min(self.chunk.len());
+        self.chunk.copy_to_slice(&mut buf[..n]);
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::write_to_path;
+    use futures_util::stream;
+
+    #[tokio::test]
+    async fn writes_and_unpacks_streams() {
+        let tmp = tempdir::TempDir::new("body").unwrap();
+
+        let mut archive = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(5);
+        header.set_mode(0o644);
+        archive
+            .append_data(&mut header, "dir/file", "hello".as_bytes())
+            .unwrap();
+        let archive = archive.into_inner().unwrap();
+        let chunks = || {
+            stream::iter(
+                archive
+                    .chunks(100)
+                    .map(|chunk| Ok(bytes::Bytes::copy_from_slice(chunk)))
+                    .collect::<Vec<_>>(),
+            )
+        };
+
+        let file = tmp.path().join("archive.tar");
+        let exported = write_to_path(chunks(), &file, false).await.unwrap();
+        assert_eq!(exported.size, archive.len() as u64);
+        assert_eq!(std::fs::read(&file).unwrap(), archive);
+        assert!(exported.digest.starts_with("sha256:") && exported.digest.len() == 71);
+        assert!(exported.verify(&exported.digest[7..]).is_ok());
+        assert!(exported.verify("sha256:1234").is_err());
+
+        let dir = tmp.path().join("unpacked");
+        let unpacked = write_to_path(chunks(), &dir, true).await.unwrap();
+        assert_eq!(unpacked.digest, exported.digest);
+        assert_eq!(
+            std::fs::read_to_string(dir.join("dir/file")).unwrap(),
+            "hello"
+        );
+
+        let failing = stream::iter(vec![
+            Ok(bytes::Bytes::from_static(b"partial")),
+            Err(crate::Error::StringError("connection reset".into())),
+        ]);
+        let failed = tmp.path().join("failed.tar");
+        assert!(write_to_path(failing, &failed, false).await.is_err());
+        assert!(!failed.exists());
+    }
+}
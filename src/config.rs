@@ -0,0 +1,517 @@
+//! Discovery of podman service connections.
+//!
+//! Connections are resolved the same way the podman CLI resolves them:
+//!  1. `CONTAINER_HOST` (with an optional `CONTAINER_SSHKEY` identity), the equivalent of `--url`
+//!  2. `CONTAINER_CONNECTION`, the equivalent of `--connection`
+//!  3. the default connection from `podman-connections.json`
+//!  4. `active_service` from the `[engine]` table of `containers.conf`
+//!  5. the rootless socket at `$XDG_RUNTIME_DIR/podman/podman.sock` followed by the rootful
+//!     socket at `/run/podman/podman.sock`
+//!
+//! Named connections are read from `podman-connections.json` and from the
+//! `[engine.service_destinations]` table of every `containers.conf` file, with the former taking
+//! precedence.
+
+use crate::{ApiVersion, Error, Podman, Result, LATEST_API_VERSION};
+
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const CONTAINER_HOST_ENV: &str = "CONTAINER_HOST";
+const CONTAINER_CONNECTION_ENV: &str = "CONTAINER_CONNECTION";
+const CONTAINER_SSHKEY_ENV: &str = "CONTAINER_SSHKEY";
+const CONTAINERS_CONF_ENV: &str = "CONTAINERS_CONF";
+const CONTAINERS_CONF_OVERRIDE_ENV: &str = "CONTAINERS_CONF_OVERRIDE";
+const PODMAN_CONNECTIONS_CONF_ENV: &str = "PODMAN_CONNECTIONS_CONF";
+
+const ROOTFUL_SOCKET: &str = "/run/podman/podman.sock";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// A podman service connection, either one of the named connections known to the podman CLI or
+/// one resolved from the environment.
+pub struct Connection {
+    /// Name of the connection, `None` when it was not resolved from a named connection.
+    pub name: Option<String>,
+    /// URI of the podman service, for example `unix:///run/podman/podman.sock` or
+    /// `ssh://core@localhost:45678/run/user/1000/podman/podman.sock`.
+    pub uri: String,
+    /// Path to the SSH private key used to authenticate.
+    pub identity: Option<PathBuf>,
+    /// Whether this connection belongs to a `podman machine` VM.
+    pub is_machine: bool,
+    /// Whether this is the default named connection.
+    pub is_default: bool,
+}
+
+impl Connection {
+    /// Creates a new [`Podman`](crate::Podman) instance connected to the service described by this
+    /// connection.
+    ///
+    /// Connections using the `ssh://` scheme require the `ssh` feature.
+    ///
+    ///  Uses [`LATEST_API_VERSION`](crate::LATEST_API_VERSION), to use a specific version see
+    ///  [`Connection::connect_versioned`](Connection::connect_versioned).
+    pub fn connect(&self) -> Result<Podman> {
+        self.connect_versioned(LATEST_API_VERSION)
+    }
+
+    /// Same as [`Connection::connect`](Connection::connect) but the API version can be explicitly
+    /// specified.
+    pub fn connect_versioned(&self, version: impl Into<ApiVersion>) -> Result<Podman> {
+        match self.uri.split_once("://") {
+            #[cfg(all(unix, feature = "ssh"))]
+            Some(("ssh", _)) => Podman::ssh_versioned(&self.uri, version, self.identity.as_ref()),
+            #[cfg(not(all(unix, feature = "ssh")))]
+            Some(("ssh", _)) => Err(Error::UnsupportedScheme("ssh".into())),
+            _ => Podman::new_versioned(&self.uri, version),
+        }
+    }
+}
+
+/// Returns all named connections known to the podman CLI sorted by name, as listed by
+/// `podman system connection list`.
+pub fn connections() -> Result<Vec<Connection>> {
+    Resolver::from_env().connections()
+}
+
+/// Looks up a named connection, the same way `podman --connection <name>` does.
+pub fn find_connection(name: impl AsRef<str>) -> Result<Connection> {
+    Resolver::from_env().find(name.as_ref())
+}
+
+/// Resolves the connection the podman CLI would use when invoked without `--url` or
+/// `--connection`. See the [module documentation](self) for the order of resolution.
+pub fn default_connection() -> Result<Connection> {
+    Resolver::from_env().default_connection()
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ContainersConf {
+    #[serde(default)]
+    engine: EngineConf,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct EngineConf {
+    active_service: Option<String>,
+    #[serde(default)]
+    service_destinations: HashMap<String, ServiceDestination>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ServiceDestination {
+    uri: String,
+    identity: Option<PathBuf>,
+    #[serde(default)]
+    is_machine: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ConnectionsFile {
+    #[serde(default)]
+    connection: ConnectionsConf,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ConnectionsConf {
+    default: Option<String>,
+    #[serde(default)]
+    connections: HashMap<String, ConnectionsDestination>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ConnectionsDestination {
+    #[serde(rename = "URI")]
+    uri: String,
+    identity: Option<PathBuf>,
+    #[serde(default)]
+    is_machine: bool,
+}
+
+#[derive(Debug, Default)]
+/// Merged view of all configuration sources.
+struct Destinations {
+    default: Option<String>,
+    destinations: BTreeMap<String, (String, Option<PathBuf>, bool)>,
+}
+
+#[derive(Debug, Default)]
+struct Resolver {
+    host: Option<String>,
+    ssh_key: Option<PathBuf>,
+    connection: Option<String>,
+    containers_confs: Vec<PathBuf>,
+    connections_conf: Option<PathBuf>,
+    sockets: Vec<PathBuf>,
+}
+
+impl Resolver {
+    fn from_env() -> Self {
+        let non_empty = |key| env::var(key).ok().filter(|v| !v.is_empty());
+        let rootless = !is_root();
+        let user_config_dir = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+            .map(|dir| dir.join("containers"));
+
+        let mut containers_confs = vec![];
+        if let Some(conf) = non_empty(CONTAINERS_CONF_ENV) {
+            containers_confs.push(PathBuf::from(conf));
+        } else {
+            let mut dirs = vec![
+                PathBuf::from("/usr/share/containers"),
+                PathBuf::from("/etc/containers"),
+            ];
+            if rootless {
+                dirs.extend(user_config_dir.clone());
+            }
+            for dir in dirs {
+                containers_confs.push(dir.join("containers.conf"));
+                containers_confs.extend(drop_in_files(&dir.join("containers.conf.d")));
+            }
+        }
+        containers_confs.extend(non_empty(CONTAINERS_CONF_OVERRIDE_ENV).map(PathBuf::from));
+
+        let connections_conf = non_empty(PODMAN_CONNECTIONS_CONF_ENV)
+            .map(PathBuf::from)
+            .or_else(|| {
+                if rootless {
+                    user_config_dir.map(|dir| dir.join("podman-connections.json"))
+                } else {
+                    Some(PathBuf::from("/etc/containers/podman-connections.json"))
+                }
+            });
+
+        let mut sockets = vec![];
+        if rootless {
+            sockets.extend(
+                env::var_os("XDG_RUNTIME_DIR")
+                    .map(PathBuf::from)
+                    .or_else(|| current_uid().map(|uid| PathBuf::from(format!("/run/user/{uid}"))))
+                    .map(|dir| dir.join("podman").join("podman.sock")),
+            );
+        }
+        sockets.push(PathBuf::from(ROOTFUL_SOCKET));
+
+        Resolver {
+            host: non_empty(CONTAINER_HOST_ENV),
+            ssh_key: non_empty(CONTAINER_SSHKEY_ENV).map(PathBuf::from),
+            connection: non_empty(CONTAINER_CONNECTION_ENV),
+            containers_confs,
+            connections_conf,
+            sockets,
+        }
+    }
+
+    fn destinations(&self) -> Result<Destinations> {
+        let mut merged = Destinations::default();
+
+        // Later files override values set by earlier ones.
+        let mut active_service = None;
+        for path in &self.containers_confs {
+            let Some(content) = read_optional(path)? else {
+                continue;
+            };
+            let conf: ContainersConf =
+                toml::from_str(&content).map_err(|e| Error::InvalidConfig {
+                    path: path.clone(),
+                    message: e.to_string(),
+                })?;
+            if conf.engine.active_service.is_some() {
+                active_service = conf.engine.active_service;
+            }
+            for (name, dest) in conf.engine.service_destinations {
+                merged
+                    .destinations
+                    .insert(name, (dest.uri, dest.identity, dest.is_machine));
+            }
+        }
+
+        let mut default = None;
+        if let Some(path) = &self.connections_conf
+            && let Some(content) = read_optional(path)?
+        {
+            let file: ConnectionsFile =
+                serde_json::from_str(&content).map_err(|e| Error::InvalidConfig {
+                    path: path.clone(),
+                    message: e.to_string(),
+                })?;
+            default = file.connection.default;
+            for (name, dest) in file.connection.connections {
+                merged
+                    .destinations
+                    .insert(name, (dest.uri, dest.identity, dest.is_machine));
+            }
+        }
+
+        merged.default = default.or(active_service);
+        Ok(merged)
+    }
+
+    fn connections(&self) -> Result<Vec<Connection>> {
+        let Destinations {
+            default,
+            destinations,
+        } = self.destinations()?;
+
+        Ok(destinations
+            .into_iter()
+            .map(|(name, (uri, identity, is_machine))| Connection {
+                is_default: default.as_deref() == Some(name.as_str()),
+                name: Some(name),
+                uri,
+                identity,
+                is_machine,
+            })
+            .collect())
+    }
+
+    fn find(&self, name: &str) -> Result<Connection> {
+        self.connections()?
+            .into_iter()
+            .find(|conn| conn.name.as_deref() == Some(name))
+            .ok_or_else(|| Error::ConnectionNotFound(name.to_string()))
+    }
+
+    fn default_connection(&self) -> Result<Connection> {
+        if let Some(host) = &self.host {
+            return Ok(Connection {
+                name: None,
+                uri: host.clone(),
+                identity: self.ssh_key.clone(),
+                is_machine: false,
+                is_default: false,
+            });
+        }
+
+        if let Some(name) = &self.connection {
+            return self.find(name);
+        }
+
+        if let Some(default) = self.destinations()?.default {
+            return self.find(&default);
+        }
+
+        self.sockets
+            .iter()
+            .find(|socket| socket.exists())
+            .map(|socket| Connection {
+                name: None,
+                uri: format!("unix://{}", socket.display()),
+                identity: None,
+                is_machine: false,
+                is_default: false,
+            })
+            .ok_or_else(|| {
+                Error::SocketNotFound(
+                    self.sockets
+                        .iter()
+                        .map(|socket| socket.display().to_string())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                )
+            })
+    }
+}
+
+fn read_optional(path: &Path) -> Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(Error::InvalidConfig {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        }),
+    }
+}
+
+/// Returns `*.conf` files from a drop-in directory in lexical order.
+fn drop_in_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<_> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| {
+                    path.extension()
+                        .map(|ext| ext == "conf")
+                        .unwrap_or_default()
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+#[cfg(unix)]
+fn current_uid() -> Option<u32> {
+    use std::os::unix::fs::MetadataExt;
+    fs::metadata("/proc/self").map(|meta| meta.uid()).ok()
+}
+
+#[cfg(not(unix))]
+fn current_uid() -> Option<u32> {
+    None
+}
+
+fn is_root() -> bool {
+    current_uid() == Some(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Connection, Resolver};
+    use crate::Error;
+    use std::path::PathBuf;
+    use tempdir::TempDir;
+
+    const CONTAINERS_CONF: &str = r#"
+[containers]
+env = [
+  "PATH=/usr/local/sbin:/usr/local/bin",
+]
+
+[engine]
+active_service = "builder"
+
+[engine.service_destinations]
+  [engine.service_destinations.builder]
+  uri = "ssh://core@build-host:22/run/user/1000/podman/podman.sock"
+  identity = "/home/user/.ssh/builder"
+
+  [engine.service_destinations.local]
+  uri = "unix:///run/podman/podman.sock"
+"#;
+
+    const CONNECTIONS_JSON: &str = r#"{
+  "Connection": {
+    "Default": "podman-machine-default",
+    "Connections": {
+      "podman-machine-default": {
+        "URI": "ssh://core@127.0.0.1:45678/run/user/501/podman/podman.sock",
+        "Identity": "/home/user/.local/share/containers/podman/machine/machine",
+        "IsMachine": true
+      }
+    }
+  },
+  "Farm": {}
+}"#;
+
+    fn resolver(tmp: &TempDir, with_json: bool) -> Resolver {
+        let conf = tmp.path().join("containers.conf");
+        std::fs::write(&conf, CONTAINERS_CONF).unwrap();
+        let json = tmp.path().join("podman-connections.json");
+        if with_json {
+            std::fs::write(&json, CONNECTIONS_JSON).unwrap();
+        }
+        Resolver {
+            containers_confs: vec![tmp.path().join("missing.conf"), conf],
+            connections_conf: Some(json),
+            sockets: vec![tmp.path().join("podman.sock")],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn lists_named_connections() {
+        let tmp = TempDir::new("podman-config").unwrap();
+        let connections = resolver(&tmp, true).connections().unwrap();
+
+        let names: Vec<_> = connections
+            .iter()
+            .map(|c| c.name.clone().unwrap())
+            .collect();
+        assert_eq!(names, ["builder", "local", "podman-machine-default"]);
+
+        let machine = &connections[2];
+        assert!(machine.is_default);
+        assert!(machine.is_machine);
+        assert_eq!(
+            machine.identity,
+            Some(PathBuf::from(
+                "/home/user/.local/share/containers/podman/machine/machine"
+            ))
+        );
+        assert!(!connections[0].is_default);
+    }
+
+    #[test]
+    fn resolves_default_connection() {
+        let tmp = TempDir::new("podman-config").unwrap();
+
+        // podman-connections.json takes precedence over containers.conf
+        let conn = resolver(&tmp, true).default_connection().unwrap();
+        assert_eq!(conn.name.as_deref(), Some("podman-machine-default"));
+
+        let tmp_no_json = TempDir::new("podman-config").unwrap();
+        let conn = resolver(&tmp_no_json, false).default_connection().unwrap();
+        assert_eq!(conn.name.as_deref(), Some("builder"));
+        assert_eq!(
+            conn.identity,
+            Some(PathBuf::from("/home/user/.ssh/builder"))
+        );
+
+        let conn = Resolver {
+            connection: Some("local".into()),
+            ..resolver(&tmp, true)
+        }
+        .default_connection()
+        .unwrap();
+        assert_eq!(conn.uri, "unix:///run/podman/podman.sock");
+
+        let conn = Resolver {
+            host: Some("tcp://127.0.0.1:8080".into()),
+            ssh_key: Some("/tmp/key".into()),
+            connection: Some("local".into()),
+            ..resolver(&tmp, true)
+        }
+        .default_connection()
+        .unwrap();
+        assert_eq!(
+            conn,
+            Connection {
+                name: None,
+                uri: "tcp://127.0.0.1:8080".into(),
+                identity: Some("/tmp/key".into()),
+                is_machine: false,
+                is_default: false,
+            }
+        );
+
+        match (Resolver {
+            connection: Some("missing".into()),
+            ..resolver(&tmp, true)
+        })
+        .default_connection()
+        {
+            Err(Error::ConnectionNotFound(name)) => assert_eq!(name, "missing"),
+            other => panic!("expected Error::ConnectionNotFound, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn falls_back_to_local_socket() {
+        let tmp = TempDir::new("podman-config").unwrap();
+        let resolver = Resolver {
+            sockets: vec![tmp.path().join("missing.sock"), tmp.path().to_path_buf()],
+            ..Default::default()
+        };
+        let conn = resolver.default_connection().unwrap();
+        assert_eq!(conn.uri, format!("unix://{}", tmp.path().display()));
+
+        let resolver = Resolver {
+            sockets: vec![tmp.path().join("missing.sock")],
+            ..Default::default()
+        };
+        assert!(matches!(
+            resolver.default_connection(),
+            Err(Error::SocketNotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,431 @@
+//! Archives used to copy paths between the host and a container with the path semantics of
+//! `podman cp`.
+
+use crate::body::{blocking_stream, read_blocking};
+use crate::{Error, Result};
+
+use bytes::Bytes;
+use futures_util::Stream;
+use std::{
+    fs,
+    io::{self, Read, Write},
+    path::{Component, Path, PathBuf},
+};
+
+/// Where a copied path ends up relative to the destination path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Destination {
+    /// The destination is an existing directory the source is copied into.
+    Into,
+    /// The source is copied to the destination path itself, replacing a file there.
+    As,
+}
+
+impl Destination {
+    /// Resolves the destination like `podman cp`. `dest_is_dir` is `None` if nothing exists at
+    /// `dest`, a file there is only replaced by a directory with `overwrite`.
+    pub(crate) fn resolve(
+        src_is_dir: bool,
+        dest: &str,
+        dest_is_dir: Option<bool>,
+        overwrite: bool,
+    ) -> Result<Self> {
+        let invalid = |message: &str| Error::InvalidCopy {
+            path: dest.into(),
+            message: message.into(),
+        };
+        match dest_is_dir {
+            Some(true) => Ok(Destination::Into),
+            Some(false) if src_is_dir && !overwrite => {
+                Err(invalid("cannot copy a directory to a file"))
+            }
+            Some(false) if dest.ends_with('/') => Err(invalid("destination is not a directory")),
+            None if !src_is_dir && dest.ends_with('/') => {
+                Err(invalid("destination directory doesn't exist"))
+            }
+            _ => Ok(Destination::As),
+        }
+    }
+}
+
+/// Returns `true` if only the content of the directory at `path` is copied as with `dir/.`.
+pub(crate) fn is_contents_only(path: &str) -> bool {
+    path == "." || path.ends_with("/.")
+}
+
+/// Splits a path in a container into its parent directory and its name, `/` has no name.
+pub(crate) fn split_container_path(path: &str) -> (String, String) {
+    let path = path.trim_end_matches('/').trim_end_matches("/.");
+    match path.rsplit_once('/') {
+        Some(("", name)) => ("/".into(), name.into()),
+        Some((parent, name)) => (parent.into(), name.into()),
+        None if path.is_empty() => ("/".into(), String::new()),
+        None => (".".into(), path.into()),
+    }
+}
+
+/// Streams a tar archive of the file or directory at `path` named `name` in the archive,
+/// keeping the mode, ownership, modification time and symlinks of every entry. Symlinks are
+/// archived as the file they point to with `follow_symlinks`.
+pub(crate) fn pack_path(
+    path: PathBuf,
+    name: String,
+    follow_symlinks: bool,
+) -> impl Stream<Item = io::Result<Bytes>> {
+    blocking_stream(move |writer| {
+        let mut archive = tar::Builder::new(writer);
+        archive.follow_symlinks(follow_symlinks);
+        let metadata = if follow_symlinks {
+            fs::metadata(&path)?
+        } else {
+            fs::symlink_metadata(&path)?
+        };
+        if metadata.is_dir() {
+            archive.append_dir_all(&name, &path)?;
+        } else {
+            archive.append_path_with_name(&path, &name)?;
+        }
+        archive.into_inner()?.flush()
+    })
+}
+
+/// Unpacks an archive of a path named `source_name` so that the path becomes `target`. The
+/// parent of `target` must exist. Directories replace files and the other way around only if
+/// `overwrite` is set.
+pub(crate) async fn unpack_path<S>(
+    stream: S,
+    source_name: String,
+    target: PathBuf,
+    overwrite: bool,
+    preserve_ownership: bool,
+) -> Result<()>
+where
+    S: Stream<Item = Result<Bytes>>,
+{
+    read_blocking(
+        stream,
+        |_| {},
+        move |reader| unpack_entries(reader, &source_name, &target, overwrite, preserve_ownership),
+    )
+    .await
+}
+
+fn unpack_entries(
+    reader: &mut dyn Read,
+    source_name: &str,
+    target: &Path,
+    overwrite: bool,
+    preserve_ownership: bool,
+) -> io::Result<()> {
+    let parent = match target.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let root = parent.canonicalize()?;
+
+    let mut archive = tar::Archive::new(&mut *reader);
+    archive.set_preserve_permissions(true);
+    archive.set_preserve_mtime(true);
+    archive.set_preserve_ownerships(preserve_ownership);
+
+    // directories are unpacked last so that their permissions and modification times are not
+    // changed by unpacking their content
+    let mut directories = vec![];
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let dest = entry_destination(&entry.path()?, source_name, target)?;
+        if let Some(dest_parent) = dest.parent() {
+            ensure_contained(dest_parent, &root)?;
+            fs::create_dir_all(dest_parent)?;
+        }
+        let entry_type = entry.header().entry_type();
+        replace_mismatched(&dest, entry_type.is_dir(), overwrite)?;
+        if entry_type.is_dir() {
+            directories.push((entry, dest));
+        } else if entry_type.is_hard_link() {
+            let link_name = entry.link_name()?.ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("hard link {dest:?} has no target"),
+                )
+            })?;
+            // the target is a path in the archive, not on the host, hard links to a symlink
+            // link the symlink itself so only its parent has to be contained
+            let original = entry_destination(&link_name, source_name, target)?;
+            if let Some(original_parent) = original.parent() {
+                ensure_contained(original_parent, &root)?;
+            }
+            if fs::symlink_metadata(&dest).is_ok() {
+                fs::remove_file(&dest)?;
+            }
+            fs::hard_link(&original, &dest)?;
+        } else {
+            entry.unpack(&dest)?;
+        }
+    }
+    for (mut entry, dest) in directories.into_iter().rev() {
+        entry.unpack(&dest)?;
+    }
+    // consume the padding after the end of the archive
+    io::copy(reader, &mut io::sink()).map(|_| ())
+}
+
+/// Where the archive entry at `path` is unpacked, its first component is the copied path
+/// itself unless the whole root directory was copied.
+fn entry_destination(path: &Path, source_name: &str, target: &Path) -> io::Result<PathBuf> {
+    let mut components = path
+        .components()
+        .filter(|component| !matches!(component, Component::CurDir));
+    if !source_name.is_empty() {
+        components.next();
+    }
+    let mut dest = target.to_path_buf();
+    for component in components {
+        match component {
+            Component::Normal(name) => dest.push(name),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid archive entry {path:?}"),
+                ))
+            }
+        }
+    }
+    Ok(dest)
+}
+
+/// Fails if the nearest existing ancestor of `path` resolves to a location outside of `root`,
+/// so that nothing is created through a symlink pointing out of the destination.
+fn ensure_contained(path: &Path, root: &Path) -> io::Result<()> {
+    let mut existing = path;
+    while fs::symlink_metadata(existing).is_err() {
+        match existing.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => existing = parent,
+            _ => {
+                existing = Path::new(".");
+                break;
+            }
+        }
+    }
+    if existing.canonicalize()?.starts_with(root) {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("archive entry {path:?} is outside of the destination"),
+        ))
+    }
+}
+
+/// Removes what exists at `dest` if it is a directory and the entry isn't or the other way
+/// around, failing unless `overwrite` is set.
+fn replace_mismatched(dest: &Path, is_dir: bool, overwrite: bool) -> io::Result<()> {
+    let Ok(metadata) = fs::metadata(dest) else {
+        return Ok(());
+    };
+    if metadata.is_dir() == is_dir {
+        return Ok(());
+    }
+    if !overwrite {
+        let (existing, replacement) = if is_dir {
+            ("file", "directory")
+        } else {
+            ("directory", "file")
+        };
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("cannot overwrite {existing} {dest:?} with a {replacement}"),
+        ));
+    }
+    if metadata.is_dir() {
+        fs::remove_dir_all(dest)
+    } else {
+        fs::remove_file(dest)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::{pack_path, split_container_path, unpack_path, Destination};
+    use futures_util::TryStreamExt;
+    use std::os::unix::fs::{symlink, MetadataExt, PermissionsExt};
+
+    fn archive_stream(
+        build: impl FnOnce(&mut tar::Builder<Vec<u8>>),
+    ) -> impl futures_util::Stream<Item = crate::Result<bytes::Bytes>> {
+        let mut builder = tar::Builder::new(vec![]);
+        build(&mut builder);
+        let bytes = bytes::Bytes::from(builder.into_inner().unwrap());
+        futures_util::stream::once(async move { Ok(bytes) })
+    }
+
+    fn header(entry_type: tar::EntryType, size: u64) -> tar::Header {
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(entry_type);
+        header.set_size(size);
+        header.set_mode(0o644);
+        header
+    }
+
+    #[test]
+    fn resolves_destinations() {
+        assert_eq!(
+            Destination::resolve(true, "/dir", Some(true), false).unwrap(),
+            Destination::Into
+        );
+        assert_eq!(
+            Destination::resolve(false, "/file", Some(false), false).unwrap(),
+            Destination::As
+        );
+        assert_eq!(
+            Destination::resolve(true, "/new", None, false).unwrap(),
+            Destination::As
+        );
+        assert!(Destination::resolve(true, "/file", Some(false), false).is_err());
+        assert_eq!(
+            Destination::resolve(true, "/file", Some(false), true).unwrap(),
+            Destination::As
+        );
+        assert!(Destination::resolve(false, "/missing/", None, false).is_err());
+
+        assert_eq!(
+            split_container_path("/etc/hosts"),
+            ("/etc".into(), "hosts".into())
+        );
+        assert_eq!(split_container_path("/etc/"), ("/".into(), "etc".into()));
+        assert_eq!(split_container_path("/etc/."), ("/".into(), "etc".into()));
+        assert_eq!(split_container_path("data"), (".".into(), "data".into()));
+        assert_eq!(split_container_path("/"), ("/".into(), String::new()));
+    }
+
+    #[tokio::test]
+    async fn packs_and_unpacks_directories() {
+        let tmp = tempdir::TempDir::new("copy").unwrap();
+        let src = tmp.path().join("src");
+        std::fs::create_dir_all(src.join("bin")).unwrap();
+        std::fs::write(src.join("bin/run.sh"), "#!/bin/sh").unwrap();
+        std::fs::set_permissions(
+            src.join("bin/run.sh"),
+            std::fs::Permissions::from_mode(0o750),
+        )
+        .unwrap();
+        symlink("bin/run.sh", src.join("run")).unwrap();
+
+        let archive = || pack_path(src.clone(), "src".into(), false).map_err(crate::Error::from);
+        let dest = tmp.path().join("dest");
+        unpack_path(archive(), "src".into(), dest.clone(), false, false)
+            .await
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dest.join("bin/run.sh")).unwrap(),
+            "#!/bin/sh"
+        );
+        let mode = std::fs::metadata(dest.join("bin/run.sh"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o750);
+        assert_eq!(
+            std::fs::read_link(dest.join("run")).unwrap(),
+            std::path::Path::new("bin/run.sh")
+        );
+
+        // a directory doesn't replace a file unless asked to
+        let file = tmp.path().join("file");
+        std::fs::write(&file, "").unwrap();
+        assert!(
+            unpack_path(archive(), "src".into(), file.clone(), false, false)
+                .await
+                .is_err()
+        );
+        unpack_path(archive(), "src".into(), file.clone(), true, false)
+            .await
+            .unwrap();
+        assert!(file.join("bin/run.sh").exists());
+    }
+
+    #[tokio::test]
+    async fn unpacks_hard_links_within_the_destination() {
+        let tmp = tempdir::TempDir::new("copy").unwrap();
+        let archive = archive_stream(|builder| {
+            let content = b"#!/bin/sh";
+            builder
+                .append_data(
+                    &mut header(tar::EntryType::Regular, content.len() as u64),
+                    "bin/sh",
+                    &content[..],
+                )
+                .unwrap();
+            builder
+                .append_link(&mut header(tar::EntryType::Link, 0), "bin/bash", "bin/sh")
+                .unwrap();
+        });
+        let dest = tmp.path().join("bin");
+        unpack_path(archive, "bin".into(), dest.clone(), false, false)
+            .await
+            .unwrap();
+        let original = std::fs::metadata(dest.join("sh")).unwrap();
+        let link = std::fs::metadata(dest.join("bash")).unwrap();
+        assert_eq!(original.ino(), link.ino());
+        assert_eq!(
+            std::fs::read_to_string(dest.join("bash")).unwrap(),
+            "#!/bin/sh"
+        );
+
+        // a hard link can't point outside of the archive
+        let archive = archive_stream(|builder| {
+            builder
+                .append_link(
+                    &mut header(tar::EntryType::Link, 0),
+                    "bin/passwd",
+                    "bin/../../etc/passwd",
+                )
+                .unwrap();
+        });
+        assert!(
+            unpack_path(
+                archive,
+                "bin".into(),
+                tmp.path().join("other"),
+                false,
+                false
+            )
+            .await
+            .is_err()
+        );
+        assert!(!tmp.path().join("other/passwd").exists());
+    }
+
+    #[tokio::test]
+    async fn doesnt_unpack_through_symlinks_out_of_the_destination() {
+        let tmp = tempdir::TempDir::new("copy").unwrap();
+        let outside = tmp.path().join("outside");
+        std::fs::create_dir(&outside).unwrap();
+        let dest_parent = tmp.path().join("dest");
+        std::fs::create_dir(&dest_parent).unwrap();
+
+        let link_target = outside.clone();
+        let archive = archive_stream(move |builder| {
+            builder
+                .append_link(
+                    &mut header(tar::EntryType::Symlink, 0),
+                    "src/link",
+                    &link_target,
+                )
+                .unwrap();
+            builder
+                .append_data(
+                    &mut header(tar::EntryType::Regular, 0),
+                    "src/link/a/b/f",
+                    &b""[..],
+                )
+                .unwrap();
+        });
+        assert!(
+            unpack_path(archive, "src".into(), dest_parent.join("src"), false, false)
+                .await
+                .is_err()
+        );
+        assert!(!outside.join("a").exists());
+    }
+}
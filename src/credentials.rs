@@ -0,0 +1,410 @@
+//! Registry credentials stored by `podman login`, `docker login` and credential helpers.
+//!
+//! Credentials are read from the same files the podman CLI uses, in order of precedence:
+//!  1. `REGISTRY_AUTH_FILE`, or `$XDG_RUNTIME_DIR/containers/auth.json` when it is not set
+//!  2. `$XDG_CONFIG_HOME/containers/auth.json`, by default `~/.config/containers/auth.json`
+//!  3. `~/.docker/config.json`
+//!
+//! In every file a registry is first looked up in `credHelpers`, then in `auths` and finally in
+//! the `credsStore` of the file. Credential helpers are invoked with the
+//! [docker-credential-helpers](https://github.com/docker/docker-credential-helpers) protocol.
+//!
+//! Use [`PodmanBuilder::registry_credentials`](crate::PodmanBuilder::registry_credentials) to
+//! attach the resolved credentials to pulls, pushes, searches and builds automatically.
+
+use crate::{
+    opts::{ImageReference, RegistryAuth},
+    Error, Result,
+};
+
+use base64::engine::{general_purpose, Engine};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+const REGISTRY_AUTH_FILE_ENV: &str = "REGISTRY_AUTH_FILE";
+
+/// Registry used for references without a registry like `alpine`.
+const DEFAULT_REGISTRY: &str = "docker.io";
+
+/// Username returned by credential helpers for identity tokens.
+const TOKEN_USERNAME: &str = "<token>";
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+/// Resolves registry credentials from auth files and credential helpers.
+pub struct CredentialStore {
+    files: Vec<PathBuf>,
+}
+
+impl CredentialStore {
+    /// Reads credentials from the files used by the podman CLI, see the
+    /// [module documentation](crate::credentials).
+    pub fn from_env() -> Self {
+        let non_empty = |key| {
+            env::var_os(key)
+                .filter(|v| !v.is_empty())
+                .map(PathBuf::from)
+        };
+        let home = non_empty("HOME");
+
+        let mut files = vec![];
+        files.extend(non_empty(REGISTRY_AUTH_FILE_ENV).or_else(|| {
+            non_empty("XDG_RUNTIME_DIR").map(|dir| dir.join("containers").join("auth.json"))
+        }));
+        files.extend(
+            non_empty("XDG_CONFIG_HOME")
+                .or_else(|| home.as_ref().map(|home| home.join(".config")))
+                .map(|dir| dir.join("containers").join("auth.json")),
+        );
+        files.extend(home.map(|home| home.join(".docker").join("config.json")));
+        Self { files }
+    }
+
+    /// Reads credentials only from the given auth files, earlier files take precedence.
+    pub fn from_files(files: impl IntoIterator<Item = impl Into<PathBuf>>) -> Self {
+        Self {
+            files: files.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Returns the credentials for `registry`, like `quay.io` or `localhost:5000`.
+    pub fn get(&self, registry: impl AsRef<str>) -> Result<Option<RegistryAuth>> {
+        let registry = normalize_registry(registry.as_ref());
+        self.lookup(&registry, &[registry.as_str()])
+    }
+
+    /// Returns the credentials used to pull or push the image `reference`, like
+    /// `quay.io/podman/hello:latest`. Credentials of a namespace like `quay.io/podman` take
+    /// precedence over the ones of the whole registry.
+    /// Image IDs and invalid references have no credentials.
+    pub fn get_for_image(&self, reference: impl AsRef<str>) -> Result<Option<RegistryAuth>> {
+        let reference = reference.as_ref().trim_start_matches("docker://");
+        let Ok(reference) = ImageReference::parse(reference) else {
+            return Ok(None);
+        };
+        let registry = reference.registry();
+        let repository = reference.name();
+        let scopes = repository
+            .match_indices('/')
+            .map(|(i, _)| &repository[..i])
+            .chain(std::iter::once(repository.as_str()))
+            .rev()
+            .collect::<Vec<_>>();
+        self.lookup(registry, &scopes)
+    }
+
+    /// Returns the credentials of every registry known to the auth files. Registries whose
+    /// credential helper fails are skipped with a warning.
+    pub fn all(&self) -> Result<BTreeMap<String, RegistryAuth>> {
+        let mut registries = vec![];
+        for file in &self.files {
+            if let Some(auth_file) = AuthFile::read(file)? {
+                registries.extend(auth_file.cred_helpers.keys().map(|r| normalize_registry(r)));
+                registries.extend(auth_file.auths.keys().map(|r| normalize_registry(r)));
+            }
+        }
+
+        let mut all = BTreeMap::new();
+        for registry in registries {
+            if all.contains_key(&registry) {
+                continue;
+            }
+            match self.get(&registry) {
+                Ok(Some(auth)) => {
+                    all.insert(registry, auth);
+                }
+                Ok(None) => {}
+                Err(e @ Error::CredentialHelper { .. }) => {
+                    log::warn!("skipping credentials of `{registry}`: {e}");
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(all)
+    }
+
+    /// Returns the value of an `X-Registry-Config` header with the credentials of every known
+    /// registry, used by builds.
+    pub(crate) fn registry_config(&self) -> Result<Option<String>> {
+        let all = self.all()?;
+        if all.is_empty() {
+            return Ok(None);
+        }
+        let json = serde_json::to_string(&all)?;
+        Ok(Some(general_purpose::URL_SAFE.encode(json)))
+    }
+
+    fn lookup(&self, registry: &str, scopes: &[&str]) -> Result<Option<RegistryAuth>> {
+        for file in &self.files {
+            let Some(auth_file) = AuthFile::read(file)? else {
+                continue;
+            };
+            if let Some(helper) = auth_file.cred_helper(registry) {
+                if let Some(auth) = run_helper(helper, registry)? {
+                    return Ok(Some(auth));
+                }
+                continue;
+            }
+            for scope in scopes {
+                if let Some(entry) = auth_file.auth(scope) {
+                    return entry.decode(file, registry).map(Some);
+                }
+            }
+            if let Some(store) = auth_file.creds_store.as_deref().filter(|s| !s.is_empty())
+                && let Some(auth) = run_helper(store, registry)?
+            {
+                return Ok(Some(auth));
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AuthFile {
+    #[serde(default)]
+    auths: HashMap<String, AuthEntry>,
+    #[serde(default, rename = "credHelpers")]
+    cred_helpers: HashMap<String, String>,
+    #[serde(rename = "credsStore")]
+    creds_store: Option<String>,
+}
+
+impl AuthFile {
+    fn read(path: &Path) -> Result<Option<Self>> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(Error::InvalidConfig {
+                    path: path.to_path_buf(),
+                    message: e.to_string(),
+                })
+            }
+        };
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| Error::InvalidConfig {
+                path: path.to_path_buf(),
+                message: e.to_string(),
+            })
+    }
+
+    fn cred_helper(&self, registry: &str) -> Option<&str> {
+        self.cred_helpers
+            .iter()
+            .find(|(key, _)| normalize_registry(key) == registry)
+            .map(|(_, helper)| helper.as_str())
+    }
+
+    fn auth(&self, scope: &str) -> Option<&AuthEntry> {
+        self.auths
+            .iter()
+            .find(|(key, _)| normalize_registry(key) == scope)
+            .map(|(_, entry)| entry)
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AuthEntry {
+    #[serde(default)]
+    auth: String,
+    #[serde(default, rename = "identitytoken")]
+    identity_token: String,
+}
+
+impl AuthEntry {
+    fn decode(&self, file: &Path, registry: &str) -> Result<RegistryAuth> {
+        if !self.identity_token.is_empty() {
+            return Ok(RegistryAuth::token(&self.identity_token));
+        }
+        let invalid = |message: &str| Error::InvalidConfig {
+            path: file.to_path_buf(),
+            message: format!("invalid credentials of `{registry}` - {message}"),
+        };
+        let decoded = general_purpose::STANDARD
+            .decode(self.auth.trim())
+            .map_err(|e| invalid(&e.to_string()))?;
+        let decoded = String::from_utf8(decoded).map_err(|e| invalid(&e.to_string()))?;
+        let (username, password) = decoded
+            .split_once(':')
+            .ok_or_else(|| invalid("expected `username:password`"))?;
+        Ok(RegistryAuth::builder()
+            .username(username)
+            .password(password)
+            .server_address(registry)
+            .build())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct HelperCredentials {
+    #[serde(rename = "Username")]
+    username: String,
+    #[serde(rename = "Secret")]
+    secret: String,
+}
+
+/// Runs `docker-credential-<helper> get` for `registry`. Returns `None` if the helper has no
+/// credentials for it.
+fn run_helper(helper: &str, registry: &str) -> Result<Option<RegistryAuth>> {
+    let program = format!("docker-credential-{helper}");
+    let failed = |message: String| Error::CredentialHelper {
+        helper: program.clone(),
+        message,
+    };
+
+    let mut child = Command::new(&program)
+        .arg("get")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| failed(e.to_string()))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(registry.as_bytes())
+            .map_err(|e| failed(e.to_string()))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| failed(e.to_string()))?;
+
+    if !output.status.success() {
+        let message = String::from_utf8_lossy(&output.stdout);
+        if message.contains("credentials not found") {
+            return Ok(None);
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(failed(format!("{} {}", message.trim(), stderr.trim())));
+    }
+
+    let credentials: HelperCredentials =
+        serde_json::from_slice(&output.stdout).map_err(|e| failed(e.to_string()))?;
+    if credentials.username == TOKEN_USERNAME {
+        return Ok(Some(RegistryAuth::token(credentials.secret)));
+    }
+    Ok(Some(
+        RegistryAuth::builder()
+            .username(credentials.username)
+            .password(credentials.secret)
+            .server_address(registry)
+            .build(),
+    ))
+}
+
+/// Normalizes a key of an auth file like `https://index.docker.io/v1/` to `docker.io`.
+fn normalize_registry(key: &str) -> String {
+    let key = key
+        .trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .trim_end_matches('/');
+    let key = key
+        .strip_suffix("/v1")
+        .or_else(|| key.strip_suffix("/v2"))
+        .unwrap_or(key);
+    match key.split_once('/') {
+        Some(("index.docker.io" | "registry-1.docker.io", rest)) => {
+            format!("{DEFAULT_REGISTRY}/{rest}")
+        }
+        None if key == "index.docker.io" || key == "registry-1.docker.io" => {
+            DEFAULT_REGISTRY.into()
+        }
+        _ => key.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CredentialStore;
+    use crate::opts::RegistryAuth;
+    use tempdir::TempDir;
+
+    const AUTH_JSON: &str = r#"{
+  "auths": {
+    "quay.io": { "auth": "dXNlcjpwYXNz" },
+    "quay.io/podman": { "auth": "cG9kbWFuOnNlY3JldA==" },
+    "localhost:5000": { "identitytoken": "abc" }
+  }
+}"#;
+
+    const DOCKER_CONFIG: &str = r#"{
+  "auths": {
+    "https://index.docker.io/v1/": { "auth": "ZG9ja2VyOmh1Yg==" },
+    "quay.io": { "auth": "aWdub3JlZDppZ25vcmVk" }
+  },
+  "credHelpers": {
+    "registry.example.com": "missing-helper"
+  }
+}"#;
+
+    fn store(tmp: &TempDir) -> CredentialStore {
+        let auth_json = tmp.path().join("auth.json");
+        std::fs::write(&auth_json, AUTH_JSON).unwrap();
+        let docker_config = tmp.path().join("config.json");
+        std::fs::write(&docker_config, DOCKER_CONFIG).unwrap();
+        CredentialStore::from_files([tmp.path().join("missing.json"), auth_json, docker_config])
+    }
+
+    fn password(auth: Option<RegistryAuth>) -> Option<(String, String)> {
+        match auth? {
+            RegistryAuth::Password {
+                username, password, ..
+            } => Some((username, password)),
+            RegistryAuth::Token { identity_token } => Some(("<token>".into(), identity_token)),
+        }
+    }
+
+    #[test]
+    fn resolves_credentials_from_auth_files() {
+        let tmp = TempDir::new("credentials").unwrap();
+        let store = store(&tmp);
+
+        assert_eq!(
+            password(store.get("quay.io").unwrap()),
+            Some(("user".into(), "pass".into()))
+        );
+        assert_eq!(
+            password(store.get_for_image("quay.io/podman/hello:latest").unwrap()),
+            Some(("podman".into(), "secret".into()))
+        );
+        assert_eq!(
+            password(store.get_for_image("quay.io/other/hello").unwrap()),
+            Some(("user".into(), "pass".into()))
+        );
+        assert_eq!(
+            password(store.get_for_image("alpine").unwrap()),
+            Some(("docker".into(), "hub".into()))
+        );
+        assert_eq!(
+            password(store.get_for_image("localhost:5000/app:1.0").unwrap()),
+            Some(("<token>".into(), "abc".into()))
+        );
+        assert!(store.get("ghcr.io").unwrap().is_none());
+        assert!(store
+            .get("registry.example.com")
+            .unwrap_err()
+            .to_string()
+            .contains("docker-credential-missing-helper"));
+
+        // a failing helper only drops its own registry
+        assert_eq!(
+            store.all().unwrap().keys().collect::<Vec<_>>(),
+            vec!["docker.io", "localhost:5000", "quay.io", "quay.io/podman"]
+        );
+        assert_eq!(
+            password(store.all().unwrap().remove("quay.io")),
+            Some(("user".into(), "pass".into()))
+        );
+        let store = CredentialStore::from_files([tmp.path().join("auth.json")]);
+        assert_eq!(
+            store.all().unwrap().keys().collect::<Vec<_>>(),
+            vec!["localhost:5000", "quay.io", "quay.io/podman"]
+        );
+        assert!(store.registry_config().unwrap().is_some());
+    }
+}
@@ -3,10 +3,17 @@
 
 #[macro_use]
 mod builder;
+mod body;
+mod copy;
 mod podman;
+#[cfg(all(unix, feature = "ssh"))]
+mod ssh;
 
 pub mod api;
+pub mod config;
+pub mod credentials;
 pub mod models;
+pub mod oci;
 pub mod opts;
 
 /// Connection related items.
@@ -17,9 +24,16 @@ pub mod conn {
 
 pub use containers_api::id::Id;
 pub use containers_api::version::{ApiVersion, Error as VersionError};
-pub use podman::Podman;
+pub use podman::{Podman, PodmanBuilder, RetryPolicy};
 
-/// Latest libpod API version supported by this crate
+/// Latest libpod API version supported by this crate, used in request paths unless
+/// [version negotiation](crate::PodmanBuilder::negotiate_version) lowers it.
+///
+/// Podman rejects request paths with a version newer than its own, so this stays at 4.3.1 which
+/// podman 4.3 and every 5.x server accept. Endpoints and options added in later versions, like
+/// the health check options of [`Container::update`](crate::api::Container::update) that need
+/// podman 5.2, are checked against the version reported by the server instead and fail with
+/// [`Error::UnsupportedByServer`](Error::UnsupportedByServer) when it is too old.
 pub const LATEST_API_VERSION: ApiVersion = ApiVersion::new(4, Some(3), Some(1));
 macro_rules! _version {
     () => {
@@ -50,8 +64,33 @@ pub enum Error {
     IO(#[from] IoError),
     #[error("The response is invalid - {0}")]
     InvalidResponse(String),
-    #[error("error {code} - {message}")]
-    Fault { code: StatusCode, message: String },
+    #[error("{error}")]
+    /// The requested resource doesn't exist.
+    NotFound {
+        /// Kind of the resource if it could be inferred from the endpoint.
+        resource: Option<api::ApiResource>,
+        /// Id or name of the resource if it could be inferred from the endpoint.
+        id: Option<String>,
+        error: ApiError,
+    },
+    #[error("{0}")]
+    /// The resource is in a state that conflicts with the request.
+    Conflict(ApiError),
+    #[error("{0}")]
+    /// The request had no effect, for example starting an already running container.
+    NotModified(ApiError),
+    #[error("{0}")]
+    /// A resource with the same name or id already exists.
+    AlreadyExists(ApiError),
+    #[error("{0}")]
+    /// The resource is used by another resource and can't be modified or removed.
+    InUse(ApiError),
+    #[error("{0}")]
+    /// Authentication with podman or a registry failed.
+    Unauthorized(ApiError),
+    #[error("{0}")]
+    /// Any other error response returned by podman.
+    Fault(ApiError),
     #[error("Provided scheme `{0}` is not supported")]
     UnsupportedScheme(String),
     #[error("Provided URI is missing authority part after scheme")]
@@ -72,6 +111,235 @@ pub enum Error {
     ServerError(#[from] models::JsonError),
     #[error("Cannot start an unchecked exec instance")]
     UncheckedExec,
+    #[error("Exec instance `{0}` was never started")]
+    ExecNotStarted(String),
+    #[error("Requires libpod API version {required} but the server only supports {actual}")]
+    UnsupportedByServer {
+        required: ApiVersion,
+        actual: ApiVersion,
+    },
+    #[error("Request timed out after {0:?}")]
+    Timeout(std::time::Duration),
+    #[cfg(all(unix, feature = "ssh"))]
+    #[error("Failed to establish SSH connection - {0}")]
+    SshConnection(String),
+    #[error("Podman connection `{0}` not found")]
+    ConnectionNotFound(String),
+    #[error("Podman socket not found, tried: {0}")]
+    SocketNotFound(String),
+    #[error("Invalid configuration file `{path}` - {message}")]
+    InvalidConfig {
+        path: std::path::PathBuf,
+        message: String,
+    },
+    #[error("Credential helper `{helper}` failed - {message}")]
+    CredentialHelper { helper: String, message: String },
+    #[error("Invalid image reference `{reference}` - {message}")]
+    InvalidReference { reference: String, message: String },
+    #[error("Digest mismatch, expected `{expected}` but got `{actual}`")]
+    DigestMismatch { expected: String, actual: String },
+    #[error("Invalid image archive - {0}")]
+    InvalidArchive(String),
+    #[error("Cannot copy to `{path}` - {message}")]
+    InvalidCopy { path: String, message: String },
+}
+
+impl Error {
+    /// Returns the details of the error response if this error was returned by podman.
+    pub fn api_error(&self) -> Option<&ApiError> {
+        match self {
+            Error::NotFound { error, .. }
+            | Error::Conflict(error)
+            | Error::NotModified(error)
+            | Error::AlreadyExists(error)
+            | Error::InUse(error)
+            | Error::Unauthorized(error)
+            | Error::Fault(error) => Some(error),
+            _ => None,
+        }
+    }
+
+    /// Returns the HTTP status code if this error was returned by podman.
+    pub fn status_code(&self) -> Option<StatusCode> {
+        self.api_error().map(|e| e.code)
+    }
+
+    /// Returns `true` if the requested resource doesn't exist.
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, Error::NotFound { .. })
+    }
+
+    /// Returns `true` if the request conflicts with the state of a resource. This includes
+    /// [`Error::AlreadyExists`](Error::AlreadyExists) and [`Error::InUse`](Error::InUse).
+    pub fn is_conflict(&self) -> bool {
+        matches!(
+            self,
+            Error::Conflict(_) | Error::AlreadyExists(_) | Error::InUse(_)
+        )
+    }
+
+    /// Returns `true` if the request had no effect.
+    pub fn is_not_modified(&self) -> bool {
+        matches!(self, Error::NotModified(_))
+    }
+
+    /// Returns `true` if a resource with the same name or id already exists.
+    pub fn is_already_exists(&self) -> bool {
+        matches!(self, Error::AlreadyExists(_))
+    }
+
+    /// Returns `true` if the resource is used by another resource.
+    pub fn is_in_use(&self) -> bool {
+        matches!(self, Error::InUse(_))
+    }
+
+    /// Returns `true` if authentication failed.
+    pub fn is_unauthorized(&self) -> bool {
+        matches!(self, Error::Unauthorized(_))
+    }
+
+    /// Returns `true` if the request or stream timed out.
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, Error::Timeout(_))
+    }
+
+    /// Returns `true` if the connection to podman couldn't be established, for example because
+    /// the service is restarting.
+    pub fn is_connect_error(&self) -> bool {
+        matches!(self, Error::Error(conn::Error::Hyper(e)) if e.is_connect())
+    }
+
+    /// Classifies an error response returned by podman.
+    pub(crate) fn from_api_error(error: ApiError) -> Self {
+        let cause = format!(
+            "{} {}",
+            error.cause.as_deref().unwrap_or_default(),
+            error.message.as_deref().unwrap_or_default()
+        )
+        .to_lowercase();
+        let already_exists = cause.contains("already exists") || cause.contains("already in use");
+        let in_use = cause.contains("in use") || cause.contains("being used");
+
+        match error.code {
+            StatusCode::NOT_FOUND => Error::NotFound {
+                resource: None,
+                id: None,
+                error,
+            },
+            StatusCode::NOT_MODIFIED => Error::NotModified(error),
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Error::Unauthorized(error),
+            StatusCode::CONFLICT | StatusCode::INTERNAL_SERVER_ERROR if already_exists => {
+                Error::AlreadyExists(error)
+            }
+            StatusCode::CONFLICT | StatusCode::INTERNAL_SERVER_ERROR if in_use => {
+                Error::InUse(error)
+            }
+            StatusCode::CONFLICT => Error::Conflict(error),
+            _ if cause.contains("unauthorized") || cause.contains("authentication required") => {
+                Error::Unauthorized(error)
+            }
+            _ => Error::Fault(error),
+        }
+    }
+
+    /// Records the endpoint that returned this error. For not found errors the resource and its
+    /// id are inferred from the endpoint as well.
+    pub(crate) fn with_endpoint(mut self, endpoint: &str) -> Self {
+        if let Error::NotFound { resource, id, .. } = &mut self {
+            let path = endpoint.split('?').next().unwrap_or_default();
+            let mut segments = path
+                .split('/')
+                .filter(|s| !s.is_empty())
+                .skip_while(|s| is_version_segment(s) || *s == "libpod")
+                .collect::<Vec<_>>();
+            if let Some((kind, rest)) = segments.split_first() {
+                *resource = api::ApiResource::from_endpoint_segment(kind);
+                // only endpoints shaped like `/{kind}/{id}/...` name a resource
+                let collection = matches!(rest, [verb] if COLLECTION_VERBS.contains(verb));
+                // names of images and manifests may contain slashes, so only a known action is
+                // dropped from the end
+                if rest.len() > 1 && rest.last().is_some_and(|verb| ACTION_VERBS.contains(verb)) {
+                    segments.pop();
+                }
+                if segments.len() > 1 && !collection {
+                    *id = Some(segments[1..].join("/"));
+                }
+            }
+        }
+        if let Some(error) = self.api_error_mut() {
+            error.endpoint = endpoint.to_string();
+        }
+        self
+    }
+
+    fn api_error_mut(&mut self) -> Option<&mut ApiError> {
+        match self {
+            Error::NotFound { error, .. }
+            | Error::Conflict(error)
+            | Error::NotModified(error)
+            | Error::AlreadyExists(error)
+            | Error::InUse(error)
+            | Error::Unauthorized(error)
+            | Error::Fault(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Endpoints of a resource kind acting on the whole collection, like `/containers/create`,
+/// rather than on a single resource.
+const COLLECTION_VERBS: [&str; 10] = [
+    "create", "json", "prune", "pull", "load", "import", "search", "export", "stats", "remove",
+];
+
+/// Endpoints acting on a single resource, like `/containers/{id}/start`.
+const ACTION_VERBS: [&str; 37] = [
+    "add", "archive", "attach", "changes", "checkpoint", "connect", "disconnect", "exec", "exists",
+    "export", "get", "healthcheck", "history", "init", "json", "kill", "logs", "mount", "pause",
+    "push", "remove", "rename", "resize", "resolve", "restart", "restore", "start", "stats", "stop",
+    "tag", "top", "tree", "unmount", "unpause", "untag", "update", "wait",
+];
+
+fn is_version_segment(segment: &str) -> bool {
+    segment
+        .strip_prefix('v')
+        .and_then(|v| v.chars().next())
+        .is_some_and(|c| c.is_ascii_digit())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Details of an error response returned by podman.
+pub struct ApiError {
+    /// HTTP status code of the response.
+    pub code: StatusCode,
+    /// Endpoint that returned the error.
+    pub endpoint: String,
+    /// Root cause of the error formatted for automated parsing.
+    pub cause: Option<String>,
+    /// Error message formatted for a human to read. If the response body couldn't be parsed this
+    /// contains the raw body.
+    pub message: Option<String>,
+    /// HTTP response code as reported in the response body.
+    pub response: Option<i64>,
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let message = match (self.message.as_deref(), self.cause.as_deref()) {
+            (None | Some(""), None | Some("")) => self
+                .code
+                .canonical_reason()
+                .unwrap_or("unknown error code")
+                .to_owned(),
+            (Some(message), None) => message.to_owned(),
+            (message, cause) => format!(
+                "{}: {}",
+                message.unwrap_or_default(),
+                cause.unwrap_or_default()
+            ),
+        };
+        write!(f, "error {} - {}", self.code, message)
+    }
 }
 
 impl Clone for Error {
@@ -2,7 +2,7 @@
 
 pub use podman_api_stubs::models::*;
 
-use crate::{Error, Result};
+use crate::{ApiVersion, Error, Result};
 
 #[cfg(feature = "chrono")]
 use {
@@ -75,6 +75,638 @@ impl TryFrom<&HeaderMap> for LibpodPingInfo {
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// Features of the podman service derived from `/libpod/_ping` and `/libpod/version`.
+pub struct Capabilities {
+    /// API version used in request paths, the lower of the client and the server versions.
+    pub api_version: ApiVersion,
+    /// Max libpod API version supported by the server.
+    pub server_api_version: ApiVersion,
+    /// Min libpod API version supported by the server.
+    pub min_api_version: Option<ApiVersion>,
+    /// Max Docker compatible API version supported by the server.
+    pub compat_api_version: String,
+    /// Version of podman running the service.
+    pub podman_version: Option<String>,
+    /// Version of buildah used to build images.
+    pub buildah_version: String,
+    /// Operating system of the server.
+    pub os: Option<String>,
+    /// Architecture of the server.
+    pub arch: Option<String>,
+}
+
+impl Capabilities {
+    pub(crate) fn new(ping: LibpodPingInfo, version: VersionResponse) -> Result<Self> {
+        let server_api_version: ApiVersion = ping
+            .libpod_api_version
+            .parse()
+            .map_err(|e| Error::InvalidResponse(format!("invalid libpod API version - {e}")))?;
+        Ok(Capabilities {
+            api_version: server_api_version,
+            server_api_version,
+            min_api_version: version.min_api_version.and_then(|v| v.parse().ok()),
+            compat_api_version: ping.api_version,
+            podman_version: version.version,
+            buildah_version: ping.libpod_buildah_version,
+            os: version.os,
+            arch: version.arch,
+        })
+    }
+
+    /// Returns `true` if the server supports the `required` libpod API version.
+    pub fn supports(&self, required: impl Into<ApiVersion>) -> bool {
+        required.into() <= self.server_api_version
+    }
+
+    /// Returns [`Error::UnsupportedByServer`](crate::Error::UnsupportedByServer) if the server
+    /// doesn't support the `required` libpod API version.
+    pub fn require(&self, required: impl Into<ApiVersion>) -> Result<()> {
+        let required = required.into();
+        if self.supports(required) {
+            Ok(())
+        } else {
+            Err(Error::UnsupportedByServer {
+                required,
+                actual: self.server_api_version,
+            })
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+/// Result of a command run with
+/// [`Container::exec_output`](crate::api::Container::exec_output).
+pub struct ExecOutput {
+    /// Exit code of the command.
+    pub exit_code: i64,
+    /// Standard output of the command. With a TTY this contains all output.
+    pub stdout: Vec<u8>,
+    /// Standard error of the command.
+    pub stderr: Vec<u8>,
+    /// Whether some output was discarded because it exceeded the output limit.
+    pub truncated: bool,
+}
+
+impl ExecOutput {
+    /// Returns `true` if the command exited with code 0.
+    pub fn success(&self) -> bool {
+        self.exit_code == 0
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// An export written to disk with
+/// [`Image::export_to_path`](crate::api::Image::export_to_path),
+/// [`Images::export_to_path`](crate::api::Images::export_to_path),
+/// [`Container::export_to_path`](crate::api::Container::export_to_path) or
+/// [`Container::checkpoint_to_path`](crate::api::Container::checkpoint_to_path).
+pub struct ExportedArchive {
+    /// The file or, for directory formats, the directory the export was written to.
+    pub path: std::path::PathBuf,
+    /// Number of bytes received from podman.
+    pub size: u64,
+    /// SHA-256 digest of the data received from podman like `sha256:...`. For directory formats
+    /// this is the digest of the tar archive of the directory.
+    pub digest: String,
+}
+
+impl ExportedArchive {
+    /// Fails with [`Error::DigestMismatch`](crate::Error::DigestMismatch) if the digest of the
+    /// export differs from `expected`, with or without the `sha256:` prefix.
+    pub fn verify(&self, expected: impl AsRef<str>) -> Result<()> {
+        let expected = expected.as_ref();
+        let actual = self.digest.trim_start_matches("sha256:");
+        if actual.eq_ignore_ascii_case(expected.trim_start_matches("sha256:")) {
+            Ok(())
+        } else {
+            Err(Error::DigestMismatch {
+                expected: expected.to_string(),
+                actual: self.digest.clone(),
+            })
+        }
+    }
+}
+
+/// Header with the base64 encoded stat of the path of an archive request.
+const PATH_STAT_HEADER: &str = "X-Docker-Container-Path-Stat";
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+/// Information about a path in a container returned by
+/// [`Container::stat_path`](crate::api::Container::stat_path).
+pub struct PathStat {
+    /// Name of the file or directory.
+    pub name: String,
+    /// Size in bytes.
+    pub size: i64,
+    /// Type and permission bits like Go's `os.FileMode`.
+    pub mode: u32,
+    /// Time of the last modification.
+    #[cfg(feature = "chrono")]
+    pub mtime: DateTime<Utc>,
+    #[cfg(not(feature = "chrono"))]
+    pub mtime: String,
+    /// The path a symlink points to, empty for other files.
+    #[serde(rename = "linkTarget", default)]
+    pub link_target: String,
+}
+
+impl PathStat {
+    /// Returns `true` if the path is a directory.
+    pub fn is_dir(&self) -> bool {
+        // os.ModeDir
+        self.mode & (1 << 31) != 0
+    }
+
+    /// Returns `true` if the path is a symlink.
+    pub fn is_symlink(&self) -> bool {
+        // os.ModeSymlink
+        self.mode & (1 << 27) != 0
+    }
+
+    /// Returns `true` if the path is a regular file.
+    pub fn is_file(&self) -> bool {
+        // any of the type bits of os.FileMode
+        self.mode & 0x8f28_0000 == 0
+    }
+
+    /// The unix permission bits like `0o755`.
+    pub fn permissions(&self) -> u32 {
+        self.mode & 0o777
+    }
+}
+
+impl TryFrom<&HeaderMap> for PathStat {
+    type Error = Error;
+
+    fn try_from(value: &HeaderMap) -> Result<Self> {
+        use base64::engine::{general_purpose, Engine};
+
+        let stat = value.get(PATH_STAT_HEADER).ok_or_else(|| {
+            Error::InvalidResponse(format!("expected `{PATH_STAT_HEADER}` field in headers"))
+        })?;
+        let stat = general_purpose::STANDARD
+            .decode(stat.as_bytes())
+            .map_err(|e| {
+                Error::InvalidResponse(format!("invalid `{PATH_STAT_HEADER}` header - {e}"))
+            })?;
+        Ok(serde_json::from_slice(&stat)?)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+/// Statistics reported by CRIU for a checkpoint or restore. Times are in microseconds, every
+/// field is only set for the operation that measures it.
+pub struct CriuStatistics {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub freezing_time: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frozen_time: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memdump_time: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memwrite_time: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pages_scanned: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pages_written: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restore_time: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub forking_time: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+/// Result of [`Container::checkpoint`](crate::api::Container::checkpoint).
+pub struct CheckpointReport {
+    #[serde(rename = "Id")]
+    /// ID of the checkpointed container.
+    pub id: String,
+    #[serde(rename = "runtime_checkpoint_duration", default)]
+    /// Time the OCI runtime took to checkpoint the container in microseconds.
+    pub runtime_duration: u64,
+    #[serde(rename = "criu_statistics")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Set if requested with
+    /// [`print_stats`](crate::opts::ContainerCheckpointOptsBuilder::print_stats).
+    pub statistics: Option<CriuStatistics>,
+}
+
+impl CheckpointReport {
+    /// Time the OCI runtime took to checkpoint the container.
+    pub fn runtime(&self) -> std::time::Duration {
+        std::time::Duration::from_micros(self.runtime_duration)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+/// Result of [`Container::restore`](crate::api::Container::restore) and
+/// [`Containers::restore_from_archive`](crate::api::Containers::restore_from_archive).
+pub struct RestoreReport {
+    #[serde(rename = "Id")]
+    /// ID of the restored container, a new container when restored from an archive.
+    pub id: String,
+    #[serde(rename = "runtime_restore_duration", default)]
+    /// Time the OCI runtime took to restore the container in microseconds.
+    pub runtime_duration: u64,
+    #[serde(rename = "criu_statistics")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Set if requested with
+    /// [`print_stats`](crate::opts::ContainerRestoreOptsBuilder::print_stats).
+    pub statistics: Option<CriuStatistics>,
+}
+
+impl RestoreReport {
+    /// Time the OCI runtime took to restore the container.
+    pub fn runtime(&self) -> std::time::Duration {
+        std::time::Duration::from_micros(self.runtime_duration)
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+/// Progress of an archive copied into a container with
+/// [`Container::copy_to_with_progress`](crate::api::Container::copy_to_with_progress).
+pub struct CopyProgress {
+    /// Bytes of the archive sent to podman so far.
+    pub bytes_sent: u64,
+    /// Whether podman has extracted the whole archive.
+    pub finished: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// A typed event of an image build parsed from the output of
+/// [`Images::build`](crate::api::Images::build).
+pub enum BuildEvent {
+    /// A build step has started. `index` starts at 1 and counts the steps of the current stage.
+    Step {
+        index: usize,
+        total: usize,
+        instruction: String,
+    },
+    /// A line of output of the current step.
+    Output(String),
+    /// The current step was reused from the cache layer with the given id.
+    CacheHit(String),
+    /// A layer or the image is being committed, with the name of the image when tagged.
+    Commit(Option<String>),
+    /// The current step was committed to the layer with the given id, reported as `--> <id>`.
+    LayerCommitted(String),
+    /// A warning reported by the build.
+    Warning(String),
+    /// The id of the built image, sent once the build has finished.
+    ImageId(String),
+    /// The build has failed.
+    Error(JsonError),
+}
+
+impl BuildEvent {
+    /// Parses the events of a single chunk of build output, one for each line. A line with the
+    /// id of an image is only recognized after a `COMMIT` or as the last line of the chunk, use
+    /// [`BuildEventParser`](BuildEventParser) to parse the whole output of a build.
+    pub fn from_response(response: &ImageBuildLibpod200Response) -> Vec<BuildEvent> {
+        let mut parser = BuildEventParser::default();
+        let mut events = parser.parse(response);
+        events.extend(parser.finish());
+        events
+    }
+
+    /// Parses a line like `STEP 2/5: RUN make`, optionally prefixed with the stage like
+    /// `[1/2] STEP 2/5: RUN make` in multi-stage builds.
+    fn parse_step(line: &str) -> Option<BuildEvent> {
+        let line = match line.strip_prefix('[') {
+            Some(staged) => staged.split_once("] ")?.1,
+            None => line,
+        };
+        let (progress, instruction) = line.strip_prefix("STEP ")?.split_once(": ")?;
+        let (index, total) = progress.split_once('/')?;
+        Some(BuildEvent::Step {
+            index: index.parse().ok()?,
+            total: total.parse().ok()?,
+            instruction: instruction.into(),
+        })
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+/// Parses the output of a build into [`BuildEvent`](BuildEvent)s, keeping track of where the
+/// build is so that output of a step is not mistaken for the id of the built image.
+pub struct BuildEventParser {
+    /// Whether the image was committed since the last step.
+    committed: bool,
+    /// A line that is the id of the image only if it turns out to be the last one.
+    pending: Option<String>,
+}
+
+impl BuildEventParser {
+    /// Parses the events of the next chunk of build output, one for each line.
+    pub fn parse(&mut self, response: &ImageBuildLibpod200Response) -> Vec<BuildEvent> {
+        let mut events = vec![];
+        for line in response
+            .stream
+            .lines()
+            .map(str::trim_end)
+            .filter(|line| !line.is_empty())
+        {
+            events.extend(self.pending.take().map(BuildEvent::Output));
+            events.extend(self.parse_line(line));
+        }
+        events
+    }
+
+    /// Ends the output of a build, returns the id of the image if it was the last line.
+    pub fn finish(&mut self) -> Option<BuildEvent> {
+        self.pending.take().map(BuildEvent::ImageId)
+    }
+
+    /// Ends the output of a failed build.
+    pub fn fail(&mut self, error: JsonError) -> Vec<BuildEvent> {
+        let mut events = self
+            .pending
+            .take()
+            .map(BuildEvent::Output)
+            .into_iter()
+            .collect::<Vec<_>>();
+        events.push(BuildEvent::Error(error));
+        events
+    }
+
+    fn parse_line(&mut self, line: &str) -> Option<BuildEvent> {
+        if let Some(step) = BuildEvent::parse_step(line) {
+            self.committed = false;
+            return Some(step);
+        }
+        if let Some(id) = line.strip_prefix("--> Using cache ") {
+            return Some(BuildEvent::CacheHit(id.trim().into()));
+        }
+        if let Some(id) = line.strip_prefix("--> ").map(str::trim)
+            && !id.is_empty()
+            && id.bytes().all(|b| b.is_ascii_hexdigit())
+        {
+            return Some(BuildEvent::LayerCommitted(id.into()));
+        }
+        if line == "COMMIT" {
+            self.committed = true;
+            return Some(BuildEvent::Commit(None));
+        }
+        if let Some(name) = line.strip_prefix("COMMIT ") {
+            self.committed = true;
+            return Some(BuildEvent::Commit(Some(name.trim().into())));
+        }
+        if ["WARN[", "WARNING", "[Warning]"]
+            .iter()
+            .any(|prefix| line.starts_with(prefix))
+        {
+            return Some(BuildEvent::Warning(line.into()));
+        }
+        if line.len() == 64 && line.bytes().all(|b| b.is_ascii_hexdigit()) {
+            // output of a step like `RUN sha256sum` looks the same as the id of the image
+            if self.committed {
+                return Some(BuildEvent::ImageId(line.into()));
+            }
+            self.pending = Some(line.into());
+            return None;
+        }
+        Some(BuildEvent::Output(line.into()))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// Progress of a multi-platform build with [`Manifests::build`](crate::api::Manifests::build).
+pub enum ManifestBuildEvent {
+    /// An event of the build for `platform`. The id of the built image is reported with
+    /// [`Built`](ManifestBuildEvent::Built) instead.
+    Build {
+        platform: crate::opts::Platform,
+        event: BuildEvent,
+    },
+    /// The image for `platform` was built.
+    Built {
+        platform: crate::opts::Platform,
+        image_id: String,
+    },
+    /// The manifest list with the given id was created once all images were built.
+    Created(String),
+    /// The image built for `platform` was added to the manifest list.
+    Added {
+        platform: crate::opts::Platform,
+        image_id: String,
+    },
+    /// The manifest list was pushed to `destination`.
+    Pushed { destination: String, digest: String },
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+/// A line of output of [`Image::push`](crate::api::Image::push).
+pub struct ImagePushReport {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Output from c/image.
+    pub stream: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Error from c/image.
+    pub error: Option<String>,
+    #[serde(rename = "manifestdigest")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Digest of the pushed manifest, sent once the push has finished.
+    pub manifest_digest: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// State of a single layer of an image transfer.
+pub enum LayerState {
+    /// The layer is known but its transfer hasn't started yet.
+    Waiting,
+    /// The layer is being copied.
+    Copying,
+    /// The layer was copied.
+    Done,
+    /// The layer wasn't copied because it already exists at the destination.
+    Skipped,
+}
+
+impl LayerState {
+    /// Returns `true` if the layer needs no more work.
+    pub fn is_finished(&self) -> bool {
+        matches!(self, LayerState::Done | LayerState::Skipped)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// Progress of a single layer of an image transfer.
+pub struct LayerProgress {
+    /// Digest of the layer as reported by podman, possibly shortened.
+    pub digest: String,
+    pub state: LayerState,
+    /// Bytes copied so far when reported by podman.
+    pub bytes_copied: Option<u64>,
+    /// Size of the layer when reported by podman.
+    pub total_bytes: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+/// Aggregated progress of an image pull or push, updated with every line reported by podman.
+///
+/// Returned by [`Images::pull_progress`](crate::api::Images::pull_progress) and
+/// [`Image::push_progress`](crate::api::Image::push_progress).
+pub struct TransferProgress {
+    /// Layers in the order they were first reported.
+    pub layers: Vec<LayerProgress>,
+    /// The last status line, like `Writing manifest to image destination`.
+    pub status: Option<String>,
+    /// IDs of the pulled images, set once a pull has finished.
+    pub image_ids: Vec<String>,
+    /// Digest of the pushed manifest, set once a push has finished.
+    pub manifest_digest: Option<String>,
+    /// Whether the transfer has finished.
+    pub finished: bool,
+}
+
+impl TransferProgress {
+    /// Number of layers that need no more work.
+    pub fn layers_finished(&self) -> usize {
+        self.layers.iter().filter(|l| l.state.is_finished()).count()
+    }
+
+    /// Sum of the bytes copied of all layers that reported them.
+    pub fn bytes_copied(&self) -> u64 {
+        self.layers.iter().filter_map(|l| l.bytes_copied).sum()
+    }
+
+    /// Sum of the sizes of all layers, if every layer reported its size.
+    pub fn total_bytes(&self) -> Option<u64> {
+        self.layers.iter().map(|l| l.total_bytes).sum()
+    }
+
+    /// Overall progress between `0.0` and `1.0`, by bytes when all layer sizes are known and
+    /// by finished layers otherwise.
+    pub fn fraction(&self) -> f64 {
+        if self.finished {
+            return 1.0;
+        }
+        match self.total_bytes() {
+            Some(total) if total > 0 => self.bytes_copied() as f64 / total as f64,
+            _ if !self.layers.is_empty() => {
+                self.layers_finished() as f64 / self.layers.len() as f64
+            }
+            _ => 0.0,
+        }
+    }
+
+    pub(crate) fn update_pull(&mut self, report: &LibpodImagesPullReport) {
+        if let Some(stream) = &report.stream {
+            stream.lines().for_each(|line| self.update(line));
+        }
+        if let Some(images) = &report.images {
+            self.image_ids = images.clone();
+            self.finished = true;
+        } else if let Some(id) = &report.id {
+            self.image_ids = vec![id.clone()];
+            self.finished = true;
+        }
+    }
+
+    pub(crate) fn update_push(&mut self, report: &ImagePushReport) {
+        if let Some(stream) = &report.stream {
+            stream.lines().for_each(|line| self.update(line));
+        }
+        if let Some(digest) = &report.manifest_digest {
+            self.manifest_digest = Some(digest.clone());
+            self.finished = true;
+        }
+    }
+
+    /// Updates the progress with a line of c/image output like
+    /// `Copying blob 4abcf2066143 done` or `Copying blob 4abcf2066143 skipped: already exists`.
+    fn update(&mut self, line: &str) {
+        let line = line.trim();
+        if line.is_empty() {
+            return;
+        }
+        self.status = Some(line.into());
+
+        let Some(blob) = line.strip_prefix("Copying blob ") else {
+            return;
+        };
+        let (digest, status) = match blob.split_once(char::is_whitespace) {
+            Some((digest, status)) => (digest, status.trim()),
+            None => (blob, ""),
+        };
+        let state = if status.starts_with("skipped") || status.contains("already exists") {
+            LayerState::Skipped
+        } else if status.starts_with("done") {
+            LayerState::Done
+        } else {
+            LayerState::Copying
+        };
+        let (bytes_copied, total_bytes) = parse_transferred_bytes(status);
+
+        let layer = match self
+            .layers
+            .iter_mut()
+            .find(|layer| same_digest(&layer.digest, digest))
+        {
+            Some(layer) => layer,
+            None => {
+                self.layers.push(LayerProgress {
+                    digest: digest.into(),
+                    state: LayerState::Waiting,
+                    bytes_copied: None,
+                    total_bytes: None,
+                });
+                self.layers.last_mut().expect("pushed layer")
+            }
+        };
+        layer.state = state;
+        layer.bytes_copied = bytes_copied.or(layer.bytes_copied);
+        layer.total_bytes = total_bytes.or(layer.total_bytes);
+        if state == LayerState::Done {
+            layer.bytes_copied = layer.total_bytes.or(layer.bytes_copied);
+        }
+    }
+}
+
+/// Compares layer digests of which either may be shortened or lack the algorithm prefix.
+fn same_digest(a: &str, b: &str) -> bool {
+    let strip = |d: &str| {
+        d.split_once(':')
+            .map(|(_, hex)| hex)
+            .unwrap_or(d)
+            .to_string()
+    };
+    let (a, b) = (strip(a), strip(b));
+    a.starts_with(&b) || b.starts_with(&a)
+}
+
+/// Parses sizes reported like `done 2.7MiB / 3.2MiB` into bytes.
+fn parse_transferred_bytes(status: &str) -> (Option<u64>, Option<u64>) {
+    let parts = status.split_whitespace().collect::<Vec<_>>();
+    match parts.iter().position(|part| *part == "/") {
+        Some(i) if i > 0 => (
+            parse_size(parts[i - 1]),
+            parts.get(i + 1).and_then(|total| parse_size(total)),
+        ),
+        _ => (None, None),
+    }
+}
+
+fn parse_size(size: &str) -> Option<u64> {
+    let unit_start = size.find(|c: char| !c.is_ascii_digit() && c != '.')?;
+    let (value, unit) = size.split_at(unit_start);
+    let multiplier: u64 = match unit {
+        "B" | "b" => 1,
+        "KiB" => 1 << 10,
+        "MiB" => 1 << 20,
+        "GiB" => 1 << 30,
+        "kB" | "KB" => 1_000,
+        "MB" => 1_000_000,
+        "GB" => 1_000_000_000,
+        _ => return None,
+    };
+    value
+        .parse::<f64>()
+        .ok()
+        .map(|value| (value * multiplier as f64) as u64)
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Event {
     #[serde(rename = "Type")]
@@ -219,6 +851,15 @@ pub struct JsonError {
     error: Option<String>,
 }
 
+impl JsonError {
+    pub(crate) fn new(error: impl Into<String>) -> Self {
+        Self {
+            error_detail: None,
+            error: Some(error.into()),
+        }
+    }
+}
+
 impl std::error::Error for JsonError {}
 
 impl fmt::Display for JsonError {
@@ -234,8 +875,199 @@ impl fmt::Display for JsonError {
             f,
             "{}{}{}",
             error,
-            if !error.is_empty() { "-" } else { "" },
+            if !error.is_empty() && !detail.is_empty() {
+                "-"
+            } else {
+                ""
+            },
             detail
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        BuildEvent, BuildEventParser, CheckpointReport, ImageBuildLibpod200Response,
+        ImagePushReport, LayerProgress, LayerState, LibpodImagesPullReport, PathStat,
+        RestoreReport, TransferProgress,
+    };
+
+    #[test]
+    fn parses_build_events() {
+        let output = "STEP 1/3: FROM alpine\n\
+                      [2/2] STEP 2/3: RUN echo hi\n\
+                      hi\n\
+                      --> Using cache 6f9c2b4a\n\
+                      WARN[0000] missing \"TAG\" build argument\n\
+                      COMMIT myimage\n\
+                      --> 9d1c6a3b4e2\n\
+                      COMMIT\n";
+        let id = "9d1c6a3b4e2f7a8b9c0d1e2f3a4b5c6d7e8f9a0b1c2d3e4f5a6b7c8d9e0f1a2b";
+        let events = [output, id, "\n"]
+            .into_iter()
+            .flat_map(|stream| {
+                BuildEvent::from_response(&ImageBuildLibpod200Response {
+                    stream: stream.into(),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            events,
+            vec![
+                BuildEvent::Step {
+                    index: 1,
+                    total: 3,
+                    instruction: "FROM alpine".into()
+                },
+                BuildEvent::Step {
+                    index: 2,
+                    total: 3,
+                    instruction: "RUN echo hi".into()
+                },
+                BuildEvent::Output("hi".into()),
+                BuildEvent::CacheHit("6f9c2b4a".into()),
+                BuildEvent::Warning("WARN[0000] missing \"TAG\" build argument".into()),
+                BuildEvent::Commit(Some("myimage".into())),
+                BuildEvent::LayerCommitted("9d1c6a3b4e2".into()),
+                BuildEvent::Commit(None),
+                BuildEvent::ImageId(id.into()),
+            ]
+        );
+
+        // output of a step looking like an image id is not the id of the image
+        let mut parser = BuildEventParser::default();
+        let mut events = vec![];
+        for stream in ["STEP 2/2: RUN sha256sum file\n", id, "\n", "COMMIT\n"] {
+            events.extend(parser.parse(&ImageBuildLibpod200Response {
+                stream: stream.into(),
+            }));
+        }
+        events.extend(parser.finish());
+        assert_eq!(events[1], BuildEvent::Output(id.into()));
+        assert_eq!(events.last(), Some(&BuildEvent::Commit(None)));
+
+        // a quiet build only outputs the id of the image
+        let mut parser = BuildEventParser::default();
+        assert!(parser
+            .parse(&ImageBuildLibpod200Response {
+                stream: format!("{id}\n"),
+            })
+            .is_empty());
+        assert_eq!(parser.finish(), Some(BuildEvent::ImageId(id.into())));
+    }
+
+    fn pull_report(stream: &str) -> LibpodImagesPullReport {
+        LibpodImagesPullReport {
+            error: None,
+            id: None,
+            images: None,
+            stream: Some(stream.into()),
+        }
+    }
+
+    #[test]
+    fn tracks_transfer_progress() {
+        let mut progress = TransferProgress::default();
+        for line in [
+            "Trying to pull docker.io/library/alpine:latest...\n",
+            "Getting image source signatures\n",
+            "Copying blob sha256:4abcf20661432fb2d719aaf90656f55c287f8ca915dc1c92ec14ff61e67fbaf8\n",
+            "Copying blob 9621f1afde84 skipped: already exists\n",
+            "Copying blob 4abcf2066143 done 1.5MiB / 3MiB\n",
+        ] {
+            progress.update_pull(&pull_report(line));
+        }
+        assert_eq!(
+            progress.layers,
+            vec![
+                LayerProgress {
+                    digest:
+                        "sha256:4abcf20661432fb2d719aaf90656f55c287f8ca915dc1c92ec14ff61e67fbaf8"
+                            .into(),
+                    state: LayerState::Done,
+                    bytes_copied: Some(3 << 20),
+                    total_bytes: Some(3 << 20),
+                },
+                LayerProgress {
+                    digest: "9621f1afde84".into(),
+                    state: LayerState::Skipped,
+                    bytes_copied: None,
+                    total_bytes: None,
+                },
+            ]
+        );
+        assert_eq!(progress.layers_finished(), 2);
+        assert_eq!(progress.total_bytes(), None);
+        assert_eq!(progress.fraction(), 1.0);
+        assert!(!progress.finished);
+
+        progress.update_pull(&LibpodImagesPullReport {
+            error: None,
+            id: Some("d4ff818577bc".into()),
+            images: Some(vec!["d4ff818577bc".into()]),
+            stream: None,
+        });
+        assert!(progress.finished);
+        assert_eq!(progress.image_ids, vec!["d4ff818577bc"]);
+
+        let mut progress = TransferProgress::default();
+        progress.update_push(&ImagePushReport {
+            stream: Some("Copying blob 5af4f8f59b76\nCopying config 8e1d7573f4\n".into()),
+            ..Default::default()
+        });
+        assert_eq!(progress.layers[0].state, LayerState::Copying);
+        assert_eq!(progress.fraction(), 0.0);
+        assert_eq!(
+            progress.status.as_deref(),
+            Some("Copying config 8e1d7573f4")
+        );
+        progress.update_push(&ImagePushReport {
+            manifest_digest: Some("sha256:1f2d".into()),
+            ..Default::default()
+        });
+        assert!(progress.finished);
+    }
+
+    #[test]
+    fn decodes_path_stat_header() {
+        use base64::engine::{general_purpose, Engine};
+
+        let stat = r#"{"name":"run","size":10,"mode":134218239,"mtime":"2024-01-02T15:04:05.5+01:00","linkTarget":"/usr/bin/run"}"#;
+        let mut headers = crate::conn::hyper::HeaderMap::new();
+        headers.insert(
+            "X-Docker-Container-Path-Stat",
+            general_purpose::STANDARD.encode(stat).parse().unwrap(),
+        );
+        let stat = PathStat::try_from(&headers).unwrap();
+        assert_eq!(stat.name, "run");
+        assert!(stat.is_symlink());
+        assert!(!stat.is_dir() && !stat.is_file());
+        assert_eq!(stat.permissions(), 0o777);
+        assert_eq!(stat.link_target, "/usr/bin/run");
+        #[cfg(feature = "chrono")]
+        assert_eq!(stat.mtime.to_rfc3339(), "2024-01-02T14:04:05.500+00:00");
+
+        assert!(PathStat::try_from(&crate::conn::hyper::HeaderMap::new()).is_err());
+    }
+
+    #[test]
+    fn parses_checkpoint_and_restore_reports() {
+        let report: CheckpointReport = serde_json::from_str(
+            r#"{"Id":"abc","runtime_checkpoint_duration":1500,"criu_statistics":{"freezing_time":10,"frozen_time":20,"memdump_time":30,"memwrite_time":40,"pages_scanned":4096,"pages_written":512}}"#,
+        )
+        .unwrap();
+        assert_eq!(report.id, "abc");
+        assert_eq!(report.runtime(), std::time::Duration::from_micros(1500));
+        let stats = report.statistics.unwrap();
+        assert_eq!(stats.pages_written, Some(512));
+        assert_eq!(stats.restore_time, None);
+
+        let report: RestoreReport =
+            serde_json::from_str(r#"{"Id":"def","runtime_restore_duration":2000}"#).unwrap();
+        assert_eq!(report.id, "def");
+        assert_eq!(report.runtime_duration, 2000);
+        assert!(report.statistics.is_none());
+    }
+}
@@ -0,0 +1,658 @@
+//! Offline inspection of image archives created by [`Image::export`](crate::api::Image::export),
+//! [`Images::export`](crate::api::Images::export) or `podman save`.
+//!
+//! Both `oci-archive` and `docker-archive` tarballs are supported. An [`ImageArchive`] indexes
+//! the entries of the tarball once so that manifests, configs and layers are read on demand
+//! without unpacking the archive or talking to podman.
+//!
+//! Example:
+//!
+//! ```no_run
+//! use podman_api::oci::ImageArchive;
+//!
+//! let archive = ImageArchive::open("/tmp/alpine.tar").unwrap();
+//! for image in archive.images().unwrap() {
+//!     println!("{:?} {:?}", image.names, image.config.architecture);
+//!     for layer in &image.manifest.layers {
+//!         for entry in archive.layer_entries(&layer.digest).unwrap() {
+//!             println!("{} {}", layer.digest, entry.path);
+//!         }
+//!     }
+//! }
+//! ```
+
+use crate::{models, Error, Result};
+
+use flate2::read::GzDecoder;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// Media type of an OCI image index.
+pub const MEDIA_TYPE_IMAGE_INDEX: &str = "application/vnd.oci.image.index.v1+json";
+/// Media type of an OCI image manifest.
+pub const MEDIA_TYPE_IMAGE_MANIFEST: &str = "application/vnd.oci.image.manifest.v1+json";
+/// Media type of a docker manifest list.
+pub const MEDIA_TYPE_DOCKER_MANIFEST_LIST: &str =
+    "application/vnd.docker.distribution.manifest.list.v2+json";
+/// Media type of an image config in a docker archive.
+pub const MEDIA_TYPE_DOCKER_CONFIG: &str = "application/vnd.docker.container.image.v1+json";
+/// Media type of an uncompressed layer in a docker archive.
+pub const MEDIA_TYPE_DOCKER_LAYER: &str = "application/vnd.docker.image.rootfs.diff.tar";
+
+/// Annotation holding the reference name of an image in an OCI layout.
+const ANNOTATION_REF_NAME: &str = "org.opencontainers.image.ref.name";
+/// Annotation holding the full image name of images exported by containerd.
+const ANNOTATION_CONTAINERD_IMAGE_NAME: &str = "io.containerd.image.name";
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The layout of an image archive.
+pub enum ArchiveFormat {
+    /// An OCI image layout with an `index.json`, created with `--format oci-archive`.
+    Oci,
+    /// A `manifest.json` in the format of `docker save`, created with `--format docker-archive`.
+    Docker,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+/// Describes content stored as a blob, see the
+/// [OCI image spec](https://github.com/opencontainers/image-spec/blob/main/descriptor.md).
+pub struct Descriptor {
+    pub media_type: String,
+    /// Digest of the content like `sha256:...`.
+    pub digest: String,
+    /// Size of the content in bytes.
+    pub size: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<HashMap<String, String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub platform: Option<models::Platform>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+/// The `index.json` of an OCI image layout or an image index blob pointing to manifests.
+pub struct Index {
+    pub schema_version: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub media_type: Option<String>,
+    pub manifests: Vec<Descriptor>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<HashMap<String, String>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+/// An image manifest listing the config and the layers of an image.
+pub struct Manifest {
+    pub schema_version: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub media_type: Option<String>,
+    pub config: Descriptor,
+    pub layers: Vec<Descriptor>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<HashMap<String, String>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// The configuration of an image with its platform, runtime config and history.
+pub struct ImageConfiguration {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub architecture: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub os: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variant: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<models::ImageConfig>,
+    pub rootfs: RootFs,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub history: Option<Vec<models::History>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// The layers of the root filesystem of an image.
+pub struct RootFs {
+    #[serde(rename = "type")]
+    pub type_: String,
+    /// Digests of the uncompressed layers.
+    pub diff_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// An image stored in an archive.
+pub struct ArchiveImage {
+    /// Names of the image like `docker.io/library/alpine:latest`, may be empty.
+    pub names: Vec<String>,
+    /// The manifest of the image. For docker archives it is assembled from `manifest.json` with
+    /// the layers identified by their uncompressed digests.
+    pub manifest: Manifest,
+    pub config: ImageConfiguration,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The kind of an entry in a layer.
+pub enum LayerEntryKind {
+    File,
+    Directory,
+    Symlink,
+    Hardlink,
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A file in a layer of an image.
+pub struct LayerEntry {
+    /// Path of the file relative to the root filesystem, without a leading `./`.
+    pub path: String,
+    pub kind: LayerEntryKind,
+    pub size: u64,
+    pub mode: u32,
+    /// Target of a symbolic or hard link.
+    pub link_target: Option<String>,
+}
+
+impl LayerEntry {
+    /// Whether this entry marks a file of a lower layer as deleted.
+    pub fn is_whiteout(&self) -> bool {
+        self.path
+            .rsplit('/')
+            .next()
+            .is_some_and(|name| name.starts_with(".wh."))
+    }
+}
+
+#[derive(Debug)]
+/// An `oci-archive` or `docker-archive` tarball opened for inspection.
+pub struct ImageArchive {
+    source: Source,
+    format: ArchiveFormat,
+    /// Offset and size of every file in the archive by path.
+    entries: HashMap<String, (u64, u64)>,
+}
+
+#[derive(Debug)]
+enum Source {
+    File(PathBuf),
+    Memory(Vec<u8>),
+}
+
+#[derive(Debug, Deserialize)]
+struct DockerManifest {
+    #[serde(rename = "Config")]
+    config: String,
+    #[serde(rename = "RepoTags", default)]
+    repo_tags: Option<Vec<String>>,
+    #[serde(rename = "Layers")]
+    layers: Vec<String>,
+}
+
+impl ImageArchive {
+    /// Opens the uncompressed archive at `path`. Only the headers of the entries are read. Use
+    /// [`ImageArchive::from_bytes`](ImageArchive::from_bytes) for a gzip compressed archive.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let entries = index_entries(fs::File::open(path)?)?;
+        Self::new(Source::File(path.to_path_buf()), entries)
+    }
+
+    /// Reads an archive from memory, the archive may be compressed with gzip.
+    pub fn from_bytes(bytes: impl Into<Vec<u8>>) -> Result<Self> {
+        let mut bytes = bytes.into();
+        if bytes.starts_with(&GZIP_MAGIC) {
+            let mut decompressed = vec![];
+            GzDecoder::new(bytes.as_slice()).read_to_end(&mut decompressed)?;
+            bytes = decompressed;
+        }
+        let entries = index_entries(io::Cursor::new(bytes.as_slice()))?;
+        Self::new(Source::Memory(bytes), entries)
+    }
+
+    fn new(source: Source, entries: HashMap<String, (u64, u64)>) -> Result<Self> {
+        let format = if entries.contains_key("index.json") {
+            ArchiveFormat::Oci
+        } else if entries.contains_key("manifest.json") {
+            ArchiveFormat::Docker
+        } else {
+            return Err(Error::InvalidArchive(
+                "neither `index.json` nor `manifest.json` found".into(),
+            ));
+        };
+        Ok(Self {
+            source,
+            format,
+            entries,
+        })
+    }
+
+    /// The layout of this archive.
+    pub fn format(&self) -> ArchiveFormat {
+        self.format.clone()
+    }
+
+    /// Paths of all files in the archive.
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(String::as_str)
+    }
+
+    /// The `index.json` of an OCI archive, `None` for docker archives.
+    pub fn index(&self) -> Result<Option<Index>> {
+        match self.format {
+            ArchiveFormat::Oci => self.read_json("index.json").map(Some),
+            ArchiveFormat::Docker => Ok(None),
+        }
+    }
+
+    /// All images stored in the archive with their manifests and configs.
+    pub fn images(&self) -> Result<Vec<ArchiveImage>> {
+        match self.format {
+            ArchiveFormat::Oci => {
+                let index: Index = self.read_json("index.json")?;
+                let mut images = vec![];
+                for descriptor in &index.manifests {
+                    self.collect_oci_images(descriptor, &mut images)?;
+                }
+                Ok(images)
+            }
+            ArchiveFormat::Docker => {
+                let manifests: Vec<DockerManifest> = self.read_json("manifest.json")?;
+                manifests
+                    .into_iter()
+                    .map(|manifest| self.docker_image(manifest))
+                    .collect()
+            }
+        }
+    }
+
+    fn collect_oci_images(
+        &self,
+        descriptor: &Descriptor,
+        images: &mut Vec<ArchiveImage>,
+    ) -> Result<()> {
+        if descriptor.media_type == MEDIA_TYPE_IMAGE_INDEX
+            || descriptor.media_type == MEDIA_TYPE_DOCKER_MANIFEST_LIST
+        {
+            let index: Index = serde_json::from_slice(&self.blob(&descriptor.digest)?)?;
+            for descriptor in &index.manifests {
+                self.collect_oci_images(descriptor, images)?;
+            }
+            return Ok(());
+        }
+        let manifest: Manifest = serde_json::from_slice(&self.blob(&descriptor.digest)?)?;
+        let config = serde_json::from_slice(&self.blob(&manifest.config.digest)?)?;
+        let names = descriptor
+            .annotations
+            .iter()
+            .flat_map(|annotations| {
+                [ANNOTATION_CONTAINERD_IMAGE_NAME, ANNOTATION_REF_NAME]
+                    .into_iter()
+                    .filter_map(|key| annotations.get(key).cloned())
+            })
+            .take(1)
+            .collect();
+        images.push(ArchiveImage {
+            names,
+            manifest,
+            config,
+        });
+        Ok(())
+    }
+
+    fn docker_image(&self, manifest: DockerManifest) -> Result<ArchiveImage> {
+        let config_bytes = self.read_entry(&manifest.config)?;
+        let config: ImageConfiguration = serde_json::from_slice(&config_bytes)?;
+        if config.rootfs.diff_ids.len() != manifest.layers.len() {
+            return Err(Error::InvalidArchive(format!(
+                "config `{}` lists {} layers but the manifest {}",
+                manifest.config,
+                config.rootfs.diff_ids.len(),
+                manifest.layers.len()
+            )));
+        }
+        // layers of docker archives are uncompressed so they are identified by their diff IDs
+        let layers = manifest
+            .layers
+            .iter()
+            .zip(&config.rootfs.diff_ids)
+            .map(|(path, diff_id)| {
+                Ok(Descriptor {
+                    media_type: MEDIA_TYPE_DOCKER_LAYER.into(),
+                    digest: diff_id.clone(),
+                    size: self.entry(path)?.1 as i64,
+                    annotations: None,
+                    platform: None,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(ArchiveImage {
+            names: manifest.repo_tags.unwrap_or_default(),
+            manifest: Manifest {
+                schema_version: 2,
+                media_type: None,
+                config: Descriptor {
+                    media_type: MEDIA_TYPE_DOCKER_CONFIG.into(),
+                    digest: docker_config_digest(&manifest.config, &config_bytes),
+                    size: config_bytes.len() as i64,
+                    annotations: None,
+                    platform: None,
+                },
+                layers,
+                annotations: None,
+            },
+            config,
+        })
+    }
+
+    /// Reads the blob with the given digest like `sha256:...`.
+    pub fn blob(&self, digest: &str) -> Result<Vec<u8>> {
+        let mut reader = self.blob_reader(digest)?;
+        let mut content = vec![];
+        reader.read_to_end(&mut content)?;
+        Ok(content)
+    }
+
+    /// Lists the files in the layer with the given digest. Layers compressed with gzip are
+    /// decompressed while they are read.
+    pub fn layer_entries(&self, digest: &str) -> Result<Vec<LayerEntry>> {
+        let mut reader = self.blob_reader(digest)?;
+        let mut magic = [0; 2];
+        let n = read_prefix(&mut reader, &mut magic)?;
+        let reader = io::Cursor::new(magic[..n].to_vec()).chain(reader);
+        if magic[..n] == GZIP_MAGIC {
+            list_layer(GzDecoder::new(reader))
+        } else {
+            list_layer(reader)
+        }
+    }
+
+    fn blob_reader(&self, digest: &str) -> Result<Box<dyn Read + '_>> {
+        let path = match self.format {
+            ArchiveFormat::Oci => {
+                let (algorithm, hex) = digest
+                    .split_once(':')
+                    .ok_or_else(|| Error::InvalidArchive(format!("invalid digest `{digest}`")))?;
+                format!("blobs/{algorithm}/{hex}")
+            }
+            ArchiveFormat::Docker => self.docker_blob_path(digest)?,
+        };
+        self.entry_reader(&path)
+    }
+
+    /// Finds a layer or config of a docker archive by its digest.
+    fn docker_blob_path(&self, digest: &str) -> Result<String> {
+        let hex = digest.trim_start_matches("sha256:");
+        for candidate in [
+            format!("blobs/sha256/{hex}"),
+            format!("{hex}.tar"),
+            format!("{hex}.json"),
+            format!("{hex}/layer.tar"),
+        ] {
+            if self.entries.contains_key(&candidate) {
+                return Ok(candidate);
+            }
+        }
+        // older archives store layers by layer ID, match them through the manifests
+        let manifests: Vec<DockerManifest> = self.read_json("manifest.json")?;
+        for manifest in manifests {
+            let config: ImageConfiguration =
+                serde_json::from_slice(&self.read_entry(&manifest.config)?)?;
+            if let Some(i) = config.rootfs.diff_ids.iter().position(|id| id == digest)
+                && let Some(path) = manifest.layers.get(i)
+            {
+                return Ok(path.clone());
+            }
+        }
+        Err(Error::InvalidArchive(format!("blob `{digest}` not found")))
+    }
+
+    fn read_json<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T> {
+        Ok(serde_json::from_slice(&self.read_entry(path)?)?)
+    }
+
+    fn read_entry(&self, path: &str) -> Result<Vec<u8>> {
+        let mut content = vec![];
+        self.entry_reader(path)?.read_to_end(&mut content)?;
+        Ok(content)
+    }
+
+    fn entry(&self, path: &str) -> Result<(u64, u64)> {
+        self.entries
+            .get(normalize_path(path))
+            .copied()
+            .ok_or_else(|| Error::InvalidArchive(format!("`{path}` not found")))
+    }
+
+    fn entry_reader(&self, path: &str) -> Result<Box<dyn Read + '_>> {
+        let (offset, size) = self.entry(path)?;
+        match &self.source {
+            Source::File(path) => {
+                let mut file = fs::File::open(path)?;
+                file.seek(SeekFrom::Start(offset))?;
+                Ok(Box::new(file.take(size)))
+            }
+            Source::Memory(bytes) => {
+                let start = offset as usize;
+                bytes
+                    .get(start..start + size as usize)
+                    .map(|content| Box::new(content) as Box<dyn Read>)
+                    .ok_or_else(|| Error::InvalidArchive(format!("`{path}` is truncated")))
+            }
+        }
+    }
+}
+
+/// Records the offset and size of every regular file in a tar archive.
+fn index_entries<R: Read + Seek>(mut reader: R) -> Result<HashMap<String, (u64, u64)>> {
+    let len = reader.seek(SeekFrom::End(0))?;
+    reader.rewind()?;
+    let mut archive = tar::Archive::new(reader);
+    let mut entries = HashMap::new();
+    for entry in archive.entries_with_seek()? {
+        let entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry.path()?.to_string_lossy().into_owned();
+        let (offset, size) = (entry.raw_file_position(), entry.size());
+        if offset.checked_add(size).is_none_or(|end| end > len) {
+            return Err(Error::InvalidArchive(format!("`{path}` is truncated")));
+        }
+        entries.insert(normalize_path(&path).to_string(), (offset, size));
+    }
+    Ok(entries)
+}
+
+fn list_layer<R: Read>(reader: R) -> Result<Vec<LayerEntry>> {
+    let mut archive = tar::Archive::new(reader);
+    let mut entries = vec![];
+    for entry in archive.entries()? {
+        let entry = entry?;
+        let header = entry.header();
+        let kind = match header.entry_type() {
+            tar::EntryType::Regular | tar::EntryType::Continuous => LayerEntryKind::File,
+            tar::EntryType::Directory => LayerEntryKind::Directory,
+            tar::EntryType::Symlink => LayerEntryKind::Symlink,
+            tar::EntryType::Link => LayerEntryKind::Hardlink,
+            _ => LayerEntryKind::Other,
+        };
+        let path = entry.path()?.to_string_lossy().into_owned();
+        entries.push(LayerEntry {
+            path: normalize_path(&path).trim_end_matches('/').to_string(),
+            kind,
+            size: entry.size(),
+            mode: header.mode()?,
+            link_target: entry
+                .link_name()?
+                .map(|target| target.to_string_lossy().into_owned()),
+        });
+    }
+    Ok(entries)
+}
+
+/// Reads up to `buf.len()` bytes, less only at the end of the reader.
+fn read_prefix(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut n = 0;
+    while n < buf.len() {
+        match reader.read(&mut buf[n..])? {
+            0 => break,
+            read => n += read,
+        }
+    }
+    Ok(n)
+}
+
+fn normalize_path(path: &str) -> &str {
+    path.trim_start_matches("./").trim_start_matches('/')
+}
+
+/// The config of a docker archive is named after its digest, others are hashed.
+fn docker_config_digest(path: &str, content: &[u8]) -> String {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    let hex = name.strip_suffix(".json").unwrap_or(name);
+    if hex.len() == 64 && hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        format!("sha256:{hex}")
+    } else {
+        use sha2::{Digest, Sha256};
+        format!("sha256:{:x}", Sha256::digest(content))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ArchiveFormat, ImageArchive, LayerEntryKind};
+    use sha2::{Digest, Sha256};
+
+    fn append(archive: &mut tar::Builder<Vec<u8>>, path: &str, content: &[u8]) {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        archive.append_data(&mut header, path, content).unwrap();
+    }
+
+    fn digest(content: &[u8]) -> String {
+        format!("sha256:{:x}", Sha256::digest(content))
+    }
+
+    fn layer() -> Vec<u8> {
+        let mut layer = tar::Builder::new(Vec::new());
+        append(&mut layer, "./etc/os-release", b"ID=test");
+        append(&mut layer, "./etc/.wh.passwd", b"");
+        layer.into_inner().unwrap()
+    }
+
+    fn config(diff_id: &str) -> Vec<u8> {
+        format!(
+            r#"{{"architecture":"amd64","os":"linux","config":{{"Cmd":["/bin/sh"]}},"rootfs":{{"type":"layers","diff_ids":["{diff_id}"]}},"history":[{{"created_by":"ADD rootfs"}}]}}"#
+        )
+        .into_bytes()
+    }
+
+    #[test]
+    fn reads_oci_archives() {
+        let layer = layer();
+        let mut compressed = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        std::io::Write::write_all(&mut compressed, &layer).unwrap();
+        let compressed = compressed.finish().unwrap();
+        let config = config(&digest(&layer));
+        let manifest = format!(
+            r#"{{"schemaVersion":2,"mediaType":"application/vnd.oci.image.manifest.v1+json","config":{{"mediaType":"application/vnd.oci.image.config.v1+json","digest":"{}","size":{}}},"layers":[{{"mediaType":"application/vnd.oci.image.layer.v1.tar+gzip","digest":"{}","size":{}}}]}}"#,
+            digest(&config),
+            config.len(),
+            digest(&compressed),
+            compressed.len()
+        );
+        let index = format!(
+            r#"{{"schemaVersion":2,"manifests":[{{"mediaType":"application/vnd.oci.image.manifest.v1+json","digest":"{}","size":{},"annotations":{{"org.opencontainers.image.ref.name":"alpine:latest"}}}}]}}"#,
+            digest(manifest.as_bytes()),
+            manifest.len()
+        );
+
+        let mut archive = tar::Builder::new(Vec::new());
+        append(
+            &mut archive,
+            "oci-layout",
+            br#"{"imageLayoutVersion":"1.0.0"}"#,
+        );
+        append(&mut archive, "index.json", index.as_bytes());
+        for blob in [manifest.as_bytes(), &config, &compressed] {
+            let path = format!("blobs/sha256/{}", &digest(blob)[7..]);
+            append(&mut archive, &path, blob);
+        }
+        let bytes = archive.into_inner().unwrap();
+
+        let tmp = tempdir::TempDir::new("oci").unwrap();
+        let path = tmp.path().join("image.tar");
+        std::fs::write(&path, &bytes).unwrap();
+
+        for archive in [
+            ImageArchive::open(&path).unwrap(),
+            ImageArchive::from_bytes(bytes.clone()).unwrap(),
+        ] {
+            assert_eq!(archive.format(), ArchiveFormat::Oci);
+            assert_eq!(archive.index().unwrap().unwrap().manifests.len(), 1);
+
+            let images = archive.images().unwrap();
+            assert_eq!(images.len(), 1);
+            assert_eq!(images[0].names, vec!["alpine:latest".to_string()]);
+            assert_eq!(images[0].config.architecture.as_deref(), Some("amd64"));
+            assert_eq!(images[0].manifest.layers[0].digest, digest(&compressed));
+
+            let entries = archive.layer_entries(&digest(&compressed)).unwrap();
+            assert_eq!(entries.len(), 2);
+            assert_eq!(entries[0].path, "etc/os-release");
+            assert_eq!(entries[0].kind, LayerEntryKind::File);
+            assert!(entries[1].is_whiteout());
+        }
+    }
+
+    #[test]
+    fn reads_docker_archives() {
+        let layer = layer();
+        let config = config(&digest(&layer));
+        let config_path = format!("{}.json", &digest(&config)[7..]);
+        let manifest = format!(
+            r#"[{{"Config":"{config_path}","RepoTags":["localhost/app:1.0"],"Layers":["abc/layer.tar"]}}]"#
+        );
+
+        let mut archive = tar::Builder::new(Vec::new());
+        append(&mut archive, "manifest.json", manifest.as_bytes());
+        append(&mut archive, &config_path, &config);
+        append(&mut archive, "abc/layer.tar", &layer);
+        let bytes = archive.into_inner().unwrap();
+
+        let mut compressed = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        std::io::Write::write_all(&mut compressed, &bytes).unwrap();
+        let archive = ImageArchive::from_bytes(compressed.finish().unwrap()).unwrap();
+        assert_eq!(archive.format(), ArchiveFormat::Docker);
+        assert!(archive.index().unwrap().is_none());
+
+        let images = archive.images().unwrap();
+        assert_eq!(images[0].names, vec!["localhost/app:1.0".to_string()]);
+        assert_eq!(images[0].manifest.config.digest, digest(&config));
+        let layer_digest = &images[0].manifest.layers[0].digest;
+        assert_eq!(layer_digest, &digest(&layer));
+        assert_eq!(archive.blob(layer_digest).unwrap(), layer);
+        assert_eq!(archive.layer_entries(layer_digest).unwrap().len(), 2);
+
+        assert!(ImageArchive::from_bytes(Vec::new()).is_err());
+
+        // entries reaching past the end of a truncated archive are rejected
+        let mut archive = tar::Builder::new(Vec::new());
+        append(&mut archive, "manifest.json", &[b' '; 4096]);
+        let mut truncated = archive.into_inner().unwrap();
+        truncated.truncate(1024);
+        assert!(matches!(
+            ImageArchive::from_bytes(truncated),
+            Err(crate::Error::InvalidArchive(_))
+        ));
+    }
+}
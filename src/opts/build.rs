@@ -0,0 +1,343 @@
+mod ignore;
+
+use crate::body::{blocking_stream, reader_stream};
+use crate::conn::hyper::Body;
+use crate::Result;
+
+use bytes::Bytes;
+use flate2::{write::GzEncoder, Compression};
+use futures_util::{AsyncRead, AsyncReadExt};
+use ignore::IgnorePatterns;
+use std::{
+    fmt, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    pin::Pin,
+};
+
+/// The build context of an image, that is the Dockerfile and all the files it references.
+///
+/// The context is streamed to podman while the build request is sent so it is never held in
+/// memory as a whole unless it was created from memory in the first place.
+pub struct BuildContext {
+    source: Source,
+}
+
+enum Source {
+    Dir(PathBuf),
+    Tar(Vec<u8>),
+    Reader(Pin<Box<dyn AsyncRead + Send>>),
+}
+
+impl fmt::Debug for BuildContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut f = f.debug_struct("BuildContext");
+        match &self.source {
+            Source::Dir(path) => f.field("dir", path),
+            Source::Tar(bytes) => f.field("tar", &format_args!("{} bytes", bytes.len())),
+            Source::Reader(_) => f.field("reader", &format_args!("..")),
+        };
+        f.finish()
+    }
+}
+
+impl BuildContext {
+    /// A context made of the files in the local directory `path`.
+    ///
+    /// Files matching the patterns of a `.containerignore` file in the directory, or a
+    /// `.dockerignore` file if there is none, are left out. The patterns can be read from another
+    /// file with [`ImageBuildOptsBuilder::ignore_file`](crate::opts::ImageBuildOptsBuilder::ignore_file).
+    /// The Dockerfile is always included.
+    pub fn dir(path: impl Into<PathBuf>) -> Self {
+        Self {
+            source: Source::Dir(path.into()),
+        }
+    }
+
+    /// A context from an existing tar archive, optionally compressed with gzip.
+    pub fn tar(bytes: impl Into<Vec<u8>>) -> Self {
+        Self {
+            source: Source::Tar(bytes.into()),
+        }
+    }
+
+    /// A context from a tar archive, optionally compressed with gzip, read from `reader` while
+    /// it is sent to podman.
+    pub fn reader(reader: impl AsyncRead + Send + 'static) -> Self {
+        Self {
+            source: Source::Reader(Box::pin(reader)),
+        }
+    }
+
+    /// Returns a new instance of a builder for a context assembled from memory.
+    pub fn builder() -> BuildContextBuilder {
+        BuildContextBuilder::default()
+    }
+
+    /// Returns `n` copies of this context to build it more than once. A context read from a
+    /// reader is read into memory first.
+    pub(crate) async fn replicate(self, n: usize) -> io::Result<Vec<BuildContext>> {
+        match self.source {
+            Source::Dir(path) => Ok(vec![path; n].into_iter().map(Self::dir).collect()),
+            Source::Tar(bytes) => Ok(vec![bytes; n].into_iter().map(Self::tar).collect()),
+            Source::Reader(mut reader) => {
+                let mut bytes = Vec::new();
+                reader.read_to_end(&mut bytes).await?;
+                Ok(vec![bytes; n].into_iter().map(Self::tar).collect())
+            }
+        }
+    }
+
+    /// The request body streaming this context. `ignore_file` overrides the ignore file of a
+    /// directory context and `dockerfiles` are never ignored.
+    pub(crate) fn into_body(self, ignore_file: Option<PathBuf>, dockerfiles: Vec<String>) -> Body {
+        match self.source {
+            Source::Dir(path) => Body::wrap_stream(dir_stream(path, ignore_file, dockerfiles)),
+            Source::Tar(bytes) => Body::from(bytes),
+            Source::Reader(reader) => Body::wrap_stream(reader_stream(reader)),
+        }
+    }
+}
+
+/// Streams a gzip compressed tar archive of the directory at `path` leaving out ignored files.
+/// The archive is created on a separate thread as it is consumed.
+fn dir_stream(
+    path: PathBuf,
+    ignore_file: Option<PathBuf>,
+    dockerfiles: Vec<String>,
+) -> impl futures_util::Stream<Item = io::Result<Bytes>> {
+    blocking_stream(move |writer| {
+        let mut ignore = IgnorePatterns::load(&path, ignore_file.as_deref())?;
+        dockerfiles
+            .iter()
+            .for_each(|dockerfile| ignore.keep(dockerfile));
+        pack_dir(writer, &path, &ignore)
+    })
+}
+
+/// Writes a gzip compressed tar archive of the directory at `path` to `writer`.
+fn pack_dir<W: Write>(writer: W, path: &Path, ignore: &IgnorePatterns) -> io::Result<()> {
+    let root = path.canonicalize()?;
+    let mut archive = tar::Builder::new(GzEncoder::new(writer, Compression::best()));
+    append_dir_entries(&mut archive, &root, "", ignore)?;
+    archive.into_inner()?.finish()?.flush()
+}
+
+fn append_dir_entries<W: Write>(
+    archive: &mut tar::Builder<W>,
+    dir: &Path,
+    relative: &str,
+    ignore: &IgnorePatterns,
+) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let name = entry.file_name();
+        let name = name.to_str().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid file name {name:?} in build context"),
+            )
+        })?;
+        let entry_relative = if relative.is_empty() {
+            name.to_string()
+        } else {
+            format!("{relative}/{name}")
+        };
+        let path = entry.path();
+        let ignored = ignore.is_ignored(&entry_relative);
+        if fs::metadata(&path)?.is_dir() {
+            // files in an ignored directory may still be included by an exclusion or be kept
+            if ignored && !ignore.may_include_beneath(&entry_relative) {
+                continue;
+            }
+            if !ignored {
+                archive.append_dir(&entry_relative, &path)?;
+            }
+            append_dir_entries(archive, &path, &entry_relative, ignore)?;
+        } else if !ignored {
+            archive.append_file(&entry_relative, &mut fs::File::open(&path)?)?;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Default, Clone)]
+/// A builder struct for a [`BuildContext`](BuildContext) assembled from files in memory.
+pub struct BuildContextBuilder {
+    files: Vec<(String, Vec<u8>, u32)>,
+}
+
+impl BuildContextBuilder {
+    /// Adds a file named `Dockerfile`, the default used by the build, with the given content.
+    pub fn dockerfile(self, content: impl Into<Vec<u8>>) -> Self {
+        self.file("Dockerfile", content)
+    }
+
+    /// Adds a file at `path` relative to the root of the context.
+    pub fn file(self, path: impl Into<String>, content: impl Into<Vec<u8>>) -> Self {
+        self.file_with_mode(path, content, 0o644)
+    }
+
+    /// Adds a file at `path` relative to the root of the context with the given permissions.
+    pub fn file_with_mode(
+        mut self,
+        path: impl Into<String>,
+        content: impl Into<Vec<u8>>,
+        mode: u32,
+    ) -> Self {
+        self.files.push((path.into(), content.into(), mode));
+        self
+    }
+
+    /// Finish building the context, fails if any of the paths is invalid.
+    pub fn build(self) -> Result<BuildContext> {
+        let mut archive = tar::Builder::new(Vec::new());
+        for (path, content, mode) in self.files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(mode);
+            archive.append_data(&mut header, path, content.as_slice())?;
+        }
+        Ok(BuildContext::tar(archive.into_inner()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{dir_stream, BuildContext, Source};
+    use futures_util::TryStreamExt;
+    use std::io::Read;
+    use std::path::PathBuf;
+
+    #[test]
+    fn builds_context_from_memory() {
+        let context = BuildContext::builder()
+            .dockerfile("FROM alpine")
+            .file_with_mode("bin/run.sh", "#!/bin/sh", 0o755)
+            .build()
+            .unwrap();
+        let Source::Tar(bytes) = context.source else {
+            panic!("expected an in-memory context");
+        };
+
+        let mut archive = tar::Archive::new(bytes.as_slice());
+        let entries = archive
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                let mut entry = entry.unwrap();
+                let mut content = String::new();
+                entry.read_to_string(&mut content).unwrap();
+                (
+                    entry.path().unwrap().to_string_lossy().into_owned(),
+                    entry.header().mode().unwrap(),
+                    content,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            entries,
+            vec![
+                ("Dockerfile".into(), 0o644, "FROM alpine".into()),
+                ("bin/run.sh".into(), 0o755, "#!/bin/sh".into()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn replicates_reader_context() {
+        let reader = futures_util::io::Cursor::new(b"archive".to_vec());
+        let contexts = BuildContext::reader(reader).replicate(2).await.unwrap();
+        assert_eq!(contexts.len(), 2);
+        for context in contexts {
+            assert!(matches!(context.source, Source::Tar(bytes) if bytes == b"archive"));
+        }
+    }
+
+    async fn stream_paths(
+        path: PathBuf,
+        ignore_file: Option<PathBuf>,
+    ) -> std::io::Result<Vec<String>> {
+        let chunks = dir_stream(path, ignore_file, vec!["Dockerfile".into()])
+            .try_collect::<Vec<_>>()
+            .await?;
+        let compressed = chunks.concat();
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(compressed.as_slice()));
+        Ok(archive
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                entry
+                    .unwrap()
+                    .path()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect())
+    }
+
+    #[tokio::test]
+    async fn streams_directory_context() {
+        let tmp = tempdir::TempDir::new("build-context").unwrap();
+        for dir in ["src", "target", ".git"] {
+            std::fs::create_dir(tmp.path().join(dir)).unwrap();
+        }
+        for (file, content) in [
+            ("Dockerfile", "FROM alpine"),
+            (".dockerignore", "target\n.git\n*.log\nDockerfile\n"),
+            ("other.ignore", "src\n"),
+            ("debug.log", ""),
+            ("src/main.rs", ""),
+            ("target/app", ""),
+            (".git/HEAD", ""),
+        ] {
+            std::fs::write(tmp.path().join(file), content).unwrap();
+        }
+        // walking an ignored directory would fail on the dangling symlink
+        #[cfg(unix)]
+        std::os::unix::fs::symlink("missing", tmp.path().join("target/dangling")).unwrap();
+
+        assert_eq!(
+            stream_paths(tmp.path().to_path_buf(), None).await.unwrap(),
+            vec![
+                ".dockerignore",
+                "Dockerfile",
+                "other.ignore",
+                "src",
+                "src/main.rs"
+            ]
+        );
+        #[cfg(unix)]
+        std::fs::remove_file(tmp.path().join("target/dangling")).unwrap();
+
+        // a .containerignore takes precedence
+        std::fs::write(tmp.path().join(".containerignore"), "*.ignore\n").unwrap();
+        assert_eq!(
+            stream_paths(tmp.path().to_path_buf(), None).await.unwrap(),
+            vec![
+                ".containerignore",
+                ".dockerignore",
+                ".git",
+                ".git/HEAD",
+                "Dockerfile",
+                "debug.log",
+                "src",
+                "src/main.rs",
+                "target",
+                "target/app"
+            ]
+        );
+
+        let ignore_file = tmp.path().join("other.ignore");
+        assert!(!stream_paths(tmp.path().to_path_buf(), Some(ignore_file))
+            .await
+            .unwrap()
+            .iter()
+            .any(|path| path.starts_with("src")));
+
+        let missing = stream_paths(tmp.path().join("missing"), None).await;
+        assert!(missing.is_err());
+    }
+}
@@ -0,0 +1,353 @@
+//! Matching of `.containerignore` and `.dockerignore` patterns, following the semantics used by
+//! `podman build` and `docker build`.
+
+use std::{fs, io, path::Path};
+
+/// Names of the ignore files looked up in the root of a build context, in order of precedence.
+const IGNORE_FILES: [&str; 2] = [".containerignore", ".dockerignore"];
+
+#[derive(Debug, Default, Clone)]
+/// Patterns excluding files from a build context.
+pub(crate) struct IgnorePatterns {
+    patterns: Vec<Pattern>,
+    /// Paths included regardless of the patterns.
+    kept: Vec<String>,
+}
+
+impl IgnorePatterns {
+    /// Loads the patterns from `ignore_file` or when not set from the first of `.containerignore`
+    /// and `.dockerignore` found in `context_dir`.
+    pub(crate) fn load(context_dir: &Path, ignore_file: Option<&Path>) -> io::Result<Self> {
+        if let Some(ignore_file) = ignore_file {
+            return fs::read_to_string(ignore_file).map(|content| Self::parse(&content));
+        }
+        for name in IGNORE_FILES {
+            match fs::read_to_string(context_dir.join(name)) {
+                Ok(content) => return Ok(Self::parse(&content)),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(Self::default())
+    }
+
+    pub(crate) fn parse(content: &str) -> Self {
+        let patterns = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let (line, exclusion) = match line.strip_prefix('!') {
+                    Some(line) => (line.trim(), true),
+                    None => (line, false),
+                };
+                let cleaned = clean(line);
+                (!cleaned.is_empty()).then(|| Pattern::parse(&cleaned, exclusion))
+            })
+            .collect();
+        Self {
+            patterns,
+            kept: vec![],
+        }
+    }
+
+    /// Makes sure `path` is never ignored, like the Dockerfile which the build can't do
+    /// without.
+    pub(crate) fn keep(&mut self, path: &str) {
+        let path = clean(path);
+        if !path.is_empty() {
+            self.kept.push(path);
+        }
+    }
+
+    /// Whether a path beneath the ignored directory `dir` may still be included by an exclusion
+    /// pattern or a kept path, so that the directory has to be walked.
+    pub(crate) fn may_include_beneath(&self, dir: &str) -> bool {
+        let prefix = format!("{dir}/");
+        self.kept.iter().any(|path| path.starts_with(&prefix))
+            || self
+                .patterns
+                .iter()
+                .any(|pattern| pattern.exclusion && pattern.may_match_beneath(&prefix))
+    }
+
+    /// Checks if `path`, relative to the root of the context and separated by `/`, is ignored.
+    /// The last pattern matching the path or any of its parent directories decides.
+    pub(crate) fn is_ignored(&self, path: &str) -> bool {
+        if self.kept.iter().any(|kept| kept == path) {
+            return false;
+        }
+        let parents = path
+            .match_indices('/')
+            .map(|(i, _)| &path[..i])
+            .collect::<Vec<_>>();
+        let mut ignored = false;
+        for pattern in &self.patterns {
+            // only patterns that can change the outcome are evaluated
+            if pattern.exclusion != ignored {
+                continue;
+            }
+            if pattern.matches(path) || parents.iter().any(|parent| pattern.matches(parent)) {
+                ignored = !pattern.exclusion;
+            }
+        }
+        ignored
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Char(char),
+    /// `?`, any character except `/`.
+    Any,
+    /// `*`, any sequence of characters except `/`.
+    AnySequence,
+    /// `**` followed by more of the pattern, any number of whole directories including none.
+    AnyDirs,
+    /// `**` at the end of the pattern, anything.
+    AnyRemaining,
+    /// `[...]`, a character in one of the ranges unless negated.
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Pattern {
+    tokens: Vec<Token>,
+    exclusion: bool,
+}
+
+impl Pattern {
+    fn parse(pattern: &str, exclusion: bool) -> Self {
+        let mut tokens = vec![];
+        let mut chars = pattern.chars().peekable();
+        while let Some(c) = chars.next() {
+            let token = match c {
+                '*' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    if chars.peek() == Some(&'/') {
+                        chars.next();
+                    }
+                    if chars.peek().is_none() {
+                        Token::AnyRemaining
+                    } else {
+                        Token::AnyDirs
+                    }
+                }
+                '*' => Token::AnySequence,
+                '?' => Token::Any,
+                '\\' => Token::Char(chars.next().unwrap_or('\\')),
+                '[' => {
+                    let negated = chars.next_if(|&c| c == '^' || c == '!').is_some();
+                    let mut ranges = vec![];
+                    let mut closed = false;
+                    while let Some(c) = chars.next() {
+                        if c == ']' && !ranges.is_empty() {
+                            closed = true;
+                            break;
+                        }
+                        let start = if c == '\\' {
+                            chars.next().unwrap_or(c)
+                        } else {
+                            c
+                        };
+                        let end = match chars.next_if_eq(&'-') {
+                            Some(_) => match chars.next() {
+                                Some(']') | None => {
+                                    ranges.push((start, start));
+                                    ranges.push(('-', '-'));
+                                    closed = true;
+                                    break;
+                                }
+                                Some('\\') => chars.next().unwrap_or('\\'),
+                                Some(end) => end,
+                            },
+                            None => start,
+                        };
+                        ranges.push((start, end));
+                    }
+                    if closed {
+                        Token::Class { negated, ranges }
+                    } else {
+                        // an unterminated class never matches, like an invalid pattern
+                        Token::Class {
+                            negated: false,
+                            ranges: vec![],
+                        }
+                    }
+                }
+                c => Token::Char(c),
+            };
+            tokens.push(token);
+        }
+        Self { tokens, exclusion }
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        let path = path.chars().collect::<Vec<_>>();
+        match_tokens(&self.tokens, &path)
+    }
+
+    /// Whether the pattern may match a path starting with `prefix`.
+    fn may_match_beneath(&self, prefix: &str) -> bool {
+        let prefix = prefix.chars().collect::<Vec<_>>();
+        match_prefix(&self.tokens, &prefix)
+    }
+}
+
+fn match_tokens(tokens: &[Token], path: &[char]) -> bool {
+    let Some((token, rest)) = tokens.split_first() else {
+        return path.is_empty();
+    };
+    match token {
+        Token::Char(c) => path.first() == Some(c) && match_tokens(rest, &path[1..]),
+        Token::Any => {
+            matches!(path.first(), Some(&c) if c != '/') && match_tokens(rest, &path[1..])
+        }
+        Token::AnySequence => {
+            let max = path.iter().position(|&c| c == '/').unwrap_or(path.len());
+            (0..=max).any(|n| match_tokens(rest, &path[n..]))
+        }
+        Token::AnyDirs => {
+            match_tokens(rest, path)
+                || path
+                    .iter()
+                    .enumerate()
+                    .filter(|&(_, &c)| c == '/')
+                    .any(|(i, _)| match_tokens(rest, &path[i + 1..]))
+        }
+        Token::AnyRemaining => true,
+        Token::Class { negated, ranges } => match path.first() {
+            Some(&c) => {
+                let in_class = ranges.iter().any(|&(start, end)| start <= c && c <= end);
+                !ranges.is_empty() && in_class != *negated && match_tokens(rest, &path[1..])
+            }
+            None => false,
+        },
+    }
+}
+
+/// Like [`match_tokens`] but succeeds once all of `path` is matched, the remaining tokens may
+/// match whatever follows it.
+fn match_prefix(tokens: &[Token], path: &[char]) -> bool {
+    if path.is_empty() {
+        return true;
+    }
+    let Some((token, rest)) = tokens.split_first() else {
+        return false;
+    };
+    match token {
+        Token::Char(c) => path[0] == *c && match_prefix(rest, &path[1..]),
+        Token::Any => path[0] != '/' && match_prefix(rest, &path[1..]),
+        Token::AnySequence => {
+            let max = path.iter().position(|&c| c == '/').unwrap_or(path.len());
+            (0..=max).any(|n| match_prefix(rest, &path[n..]))
+        }
+        Token::AnyDirs => {
+            match_prefix(rest, path)
+                || path
+                    .iter()
+                    .enumerate()
+                    .filter(|&(_, &c)| c == '/')
+                    .any(|(i, _)| match_prefix(rest, &path[i + 1..]))
+        }
+        Token::AnyRemaining => true,
+        Token::Class { negated, ranges } => {
+            let in_class = ranges
+                .iter()
+                .any(|&(start, end)| start <= path[0] && path[0] <= end);
+            !ranges.is_empty() && in_class != *negated && match_prefix(rest, &path[1..])
+        }
+    }
+}
+
+/// Normalizes a pattern or path like `filepath.Clean`, relative to the root of the context.
+fn clean(path: &str) -> String {
+    let mut segments: Vec<&str> = vec![];
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                if segments.last().is_some_and(|s| *s != "..") {
+                    segments.pop();
+                } else if !path.starts_with('/') {
+                    segments.push(segment);
+                }
+            }
+            segment => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IgnorePatterns;
+
+    #[test]
+    fn matches_ignore_patterns() {
+        let patterns = IgnorePatterns::parse(
+            "# comment\n\
+             \n\
+             /target\n\
+             .git\n\
+             **/node_modules\n\
+             *.log\n\
+             !important.log\n\
+             docs/**/*.md\n\
+             !docs/README.md\n\
+             tmp?\n\
+             [a-c]dir/\n\
+             build/**\n",
+        );
+
+        for ignored in [
+            "target",
+            "target/debug/app",
+            ".git/HEAD",
+            "node_modules/dep/index.js",
+            "web/app/node_modules/dep",
+            "error.log",
+            "docs/guide.md",
+            "docs/api/v1/index.md",
+            "tmp1",
+            "bdir/file",
+            "build/out/bin",
+        ] {
+            assert!(patterns.is_ignored(ignored), "{ignored} should be ignored");
+        }
+        for kept in [
+            "src/main.rs",
+            "src/target",
+            "logs/error.log",
+            "important.log",
+            "docs/README.md",
+            "docs/guide.txt",
+            "tmp12",
+            "ddir/file",
+            "build",
+        ] {
+            assert!(!patterns.is_ignored(kept), "{kept} should be kept");
+        }
+        // only `!docs/README.md` can include anything beneath an ignored directory
+        assert!(patterns.may_include_beneath("docs"));
+        assert!(!patterns.may_include_beneath("target"));
+        assert!(!patterns.may_include_beneath("web/app/node_modules"));
+    }
+
+    #[test]
+    fn keeps_files_needed_by_build() {
+        let mut patterns = IgnorePatterns::parse("*\n");
+        assert!(patterns.is_ignored("Dockerfile"));
+
+        patterns.keep("./Dockerfile");
+        patterns.keep("build/Containerfile");
+        assert!(!patterns.is_ignored("Dockerfile"));
+        assert!(!patterns.is_ignored("build/Containerfile"));
+        assert!(patterns.is_ignored("src"));
+        assert!(!patterns.may_include_beneath("src"));
+        assert!(patterns.may_include_beneath("build"));
+    }
+}
@@ -1,12 +1,15 @@
 use crate::models;
-use crate::opts::ImageOpt;
+use crate::opts::{ImageOpt, PullPolicy, RegistryAuth};
+use crate::ApiVersion;
 use containers_api::opts::{Filter, FilterItem};
 use containers_api::{
     impl_field, impl_filter_func, impl_map_field, impl_opts_builder, impl_str_enum_field,
     impl_str_field, impl_url_bool_field, impl_url_field, impl_url_str_field, impl_url_vec_field,
     impl_vec_field,
 };
+use std::collections::BTreeMap;
 use std::fmt;
+use std::time::Duration;
 
 impl_opts_builder!(url =>
     /// Adjust the list of returned containers with this options.
@@ -260,19 +263,103 @@ impl ContainerCommitOptsBuilder {
     );
 }
 
-impl_opts_builder!(url =>
-    /// Adjust how to wait for a container.
-    ContainerWait
-);
+#[derive(Debug, Clone, PartialEq)]
+/// Condition to wait for with [`Container::wait`](crate::api::Container::wait).
+pub enum WaitCondition {
+    /// The container reached a given status.
+    Status(models::ContainerStatus),
+    /// The healthcheck of the container reports it healthy.
+    Healthy,
+    /// The healthcheck of the container reports it unhealthy.
+    Unhealthy,
+}
+
+impl From<models::ContainerStatus> for WaitCondition {
+    fn from(status: models::ContainerStatus) -> Self {
+        WaitCondition::Status(status)
+    }
+}
+
+impl AsRef<str> for WaitCondition {
+    fn as_ref(&self) -> &str {
+        match self {
+            WaitCondition::Status(status) => status.as_ref(),
+            WaitCondition::Healthy => "healthy",
+            WaitCondition::Unhealthy => "unhealthy",
+        }
+    }
+}
+
+impl fmt::Display for WaitCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_ref())
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+/// Adjust how to wait for a container.
+pub struct ContainerWaitOpts {
+    params: BTreeMap<&'static str, String>,
+    vec_params: BTreeMap<&'static str, Vec<String>>,
+    pub(crate) timeout: Option<Duration>,
+}
+
+impl ContainerWaitOpts {
+    /// Returns a new instance of a builder for ContainerWaitOpts.
+    pub fn builder() -> ContainerWaitOptsBuilder {
+        ContainerWaitOptsBuilder::default()
+    }
+
+    /// Serialize options as a URL query String. Returns None if no options are defined.
+    pub fn serialize(&self) -> Option<String> {
+        let mut serialized = containers_api::url::encoded_pairs(&self.params);
+        let vec_params = containers_api::url::encoded_vec_pairs(&self.vec_params);
+
+        if !vec_params.is_empty() {
+            if !serialized.is_empty() {
+                serialized.push('&');
+            }
+            serialized.push_str(&vec_params);
+        }
+
+        if serialized.is_empty() {
+            None
+        } else {
+            Some(serialized)
+        }
+    }
+
+    /// Replaces the conditions with a single `condition`.
+    pub(crate) fn with_condition(&self, condition: WaitCondition) -> Self {
+        let mut new = self.clone();
+        new.vec_params
+            .insert("condition", vec![condition.as_ref().into()]);
+        new
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+/// A builder struct for ContainerWaitOpts.
+pub struct ContainerWaitOptsBuilder {
+    params: BTreeMap<&'static str, String>,
+    vec_params: BTreeMap<&'static str, Vec<String>>,
+    timeout: Option<Duration>,
+}
 
 impl ContainerWaitOptsBuilder {
+    /// Conditions to wait for, either a [`ContainerStatus`](models::ContainerStatus) or a
+    /// [`WaitCondition`](WaitCondition). The wait finishes once any of them is met. By default
+    /// podman waits for the container to stop.
     pub fn conditions(
         mut self,
-        conditions: impl IntoIterator<Item = models::ContainerStatus>,
+        conditions: impl IntoIterator<Item = impl Into<WaitCondition>>,
     ) -> Self {
         self.vec_params.insert(
             "condition",
-            conditions.into_iter().map(|c| c.as_ref().into()).collect(),
+            conditions
+                .into_iter()
+                .map(|c| c.into().as_ref().into())
+                .collect(),
         );
         self
     }
@@ -281,6 +368,114 @@ impl ContainerWaitOptsBuilder {
         /// Time Interval to wait before polling for completion. Example: `250ms`, `2s`
         interval => "interval"
     );
+
+    /// Maximum time to wait for the condition. Once it elapses the wait fails with
+    /// [`Error::Timeout`](crate::Error::Timeout). The timeout is enforced by the client so it
+    /// also applies when the connection to podman stops responding.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Finish building ContainerWaitOpts.
+    pub fn build(self) -> ContainerWaitOpts {
+        ContainerWaitOpts {
+            params: self.params,
+            vec_params: self.vec_params,
+            timeout: self.timeout,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Adjust how a container is run with [`Containers::run`](crate::api::Containers::run).
+pub struct ContainerRunOpts {
+    create: ContainerCreateOpts,
+    pub(crate) pull_policy: PullPolicy,
+    pub(crate) auth: Option<RegistryAuth>,
+    pub(crate) remove: bool,
+    pub(crate) detach: bool,
+}
+
+impl ContainerRunOpts {
+    /// Returns a new instance of a builder for ContainerRunOpts. The container is created from
+    /// `create`.
+    pub fn builder(create: ContainerCreateOpts) -> ContainerRunOptsBuilder {
+        ContainerRunOptsBuilder {
+            create,
+            pull_policy: PullPolicy::Missing,
+            auth: None,
+            remove: false,
+            detach: false,
+        }
+    }
+
+    /// The image the container is created from.
+    pub(crate) fn image(&self) -> Option<&str> {
+        self.create
+            .params
+            .get("image")
+            .and_then(serde_json::Value::as_str)
+    }
+
+    /// Options used to create the container. A detached container is removed by podman itself.
+    pub(crate) fn create_opts(&self) -> ContainerCreateOpts {
+        let mut create = self.create.clone();
+        if self.detach && self.remove {
+            create.params.insert("remove", true.into());
+        }
+        create
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A builder struct for ContainerRunOpts.
+pub struct ContainerRunOptsBuilder {
+    create: ContainerCreateOpts,
+    pull_policy: PullPolicy,
+    auth: Option<RegistryAuth>,
+    remove: bool,
+    detach: bool,
+}
+
+impl ContainerRunOptsBuilder {
+    /// When to pull the image of the container. Defaults to
+    /// [`PullPolicy::Missing`](PullPolicy::Missing).
+    pub fn pull_policy(mut self, policy: PullPolicy) -> Self {
+        self.pull_policy = policy;
+        self
+    }
+
+    /// Authentication used when pulling the image.
+    pub fn auth(mut self, auth: RegistryAuth) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    /// Remove the container once it exits.
+    pub fn remove(mut self, remove: bool) -> Self {
+        self.remove = remove;
+        self
+    }
+
+    /// Leave the container running in the background. The exit status of a detached container
+    /// isn't collected and with [`remove`](ContainerRunOptsBuilder::remove) podman removes the
+    /// container once it exits.
+    pub fn detach(mut self, detach: bool) -> Self {
+        self.detach = detach;
+        self
+    }
+
+    /// Finish building ContainerRunOpts.
+    pub fn build(self) -> ContainerRunOpts {
+        ContainerRunOpts {
+            create: self.create,
+            pull_policy: self.pull_policy,
+            auth: self.auth,
+            remove: self.remove,
+            detach: self.detach,
+        }
+    }
 }
 
 impl_opts_builder!(json =>
@@ -288,6 +483,19 @@ impl_opts_builder!(json =>
     ContainerCreate
 );
 
+impl ContainerCreateOpts {
+    /// Lowest libpod API version supporting all fields that were set.
+    pub(crate) fn required_api_version(&self) -> Option<ApiVersion> {
+        super::required_api_version(
+            &self.params,
+            &[(
+                "health_check_on_failure_action",
+                ApiVersion::new(4, Some(3), None),
+            )],
+        )
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 /// Mode used to configure image volume with
 /// [`image_volume_mode`](ContainerCreateOptsBuilder::image_volume_mode).
@@ -400,7 +608,8 @@ impl fmt::Display for SystemdEnabled {
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 /// Used with
-/// [`ContainerCreateOptsBuilder::restart_policy`](ContainerCreateOptsBuilder::restart_policy).
+/// [`ContainerCreateOptsBuilder::restart_policy`](ContainerCreateOptsBuilder::restart_policy) and
+/// [`ContainerUpdateOptsBuilder::restart_policy`](ContainerUpdateOptsBuilder::restart_policy).
 #[derive(Default)]
 pub enum ContainerRestartPolicy {
     Always,
@@ -1060,6 +1269,272 @@ impl ContainerCreateOptsBuilder {
     );
 }
 
+impl_opts_builder!(json =>
+    /// Adjust the resource limits and configuration of an existing container with
+    /// [`Container::update`](crate::api::Container::update).
+    ContainerUpdate
+);
+
+/// Fields of [`LinuxResources`](models::LinuxResources) sent as the body of an update.
+const RESOURCE_FIELDS: &[&str] = &[
+    "blockIO",
+    "cpu",
+    "devices",
+    "hugepageLimits",
+    "memory",
+    "network",
+    "pids",
+    "rdma",
+    "unified",
+];
+
+/// Parameters of an update sent in the query rather than the body.
+const UPDATE_QUERY_PARAMS: &[&str] = &["restartPolicy", "restartRetries"];
+
+impl ContainerUpdateOpts {
+    /// Lowest libpod API version supporting the update with all fields that were set.
+    pub(crate) fn required_api_version(&self) -> ApiVersion {
+        super::required_api_version(
+            &self.params,
+            &[
+                ("restartPolicy", ApiVersion::new(5, Some(0), None)),
+                ("restartRetries", ApiVersion::new(5, Some(0), None)),
+                ("health_cmd", ApiVersion::new(5, Some(2), None)),
+                ("health_interval", ApiVersion::new(5, Some(2), None)),
+                ("health_on_failure", ApiVersion::new(5, Some(2), None)),
+                ("health_retries", ApiVersion::new(5, Some(2), None)),
+                ("health_start_period", ApiVersion::new(5, Some(2), None)),
+                ("health_timeout", ApiVersion::new(5, Some(2), None)),
+                ("no_healthcheck", ApiVersion::new(5, Some(2), None)),
+            ],
+        )
+        .unwrap_or(ApiVersion::new(4, Some(3), None))
+    }
+
+    /// The restart policy which is passed as query parameters.
+    pub(crate) fn query(&self) -> Option<String> {
+        let pairs = UPDATE_QUERY_PARAMS
+            .iter()
+            .filter_map(|key| {
+                self.params.get(key).map(|value| match value {
+                    serde_json::Value::String(value) => (*key, value.clone()),
+                    value => (*key, value.to_string()),
+                })
+            })
+            .collect::<Vec<_>>();
+        (!pairs.is_empty()).then(|| containers_api::url::encoded_pairs(pairs))
+    }
+
+    /// The body of the update with the resources and health check configuration.
+    pub(crate) fn body(&self) -> crate::Result<Vec<u8>> {
+        let body = self
+            .params
+            .iter()
+            .filter(|(key, _)| !UPDATE_QUERY_PARAMS.contains(key))
+            .collect::<BTreeMap<_, _>>();
+        serde_json::to_vec(&body).map_err(crate::Error::from)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Used with
+/// [`ContainerUpdateOptsBuilder::health_on_failure`](ContainerUpdateOptsBuilder::health_on_failure).
+pub enum HealthCheckOnFailureAction {
+    None,
+    Kill,
+    Restart,
+    Stop,
+}
+
+impl AsRef<str> for HealthCheckOnFailureAction {
+    fn as_ref(&self) -> &str {
+        match self {
+            HealthCheckOnFailureAction::None => "none",
+            HealthCheckOnFailureAction::Kill => "kill",
+            HealthCheckOnFailureAction::Restart => "restart",
+            HealthCheckOnFailureAction::Stop => "stop",
+        }
+    }
+}
+
+impl fmt::Display for HealthCheckOnFailureAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_ref())
+    }
+}
+
+impl ContainerUpdateOptsBuilder {
+    /// Set all limits of `resources`, the same as
+    /// [`ContainerCreateOptsBuilder::resource_limits`](ContainerCreateOptsBuilder::resource_limits).
+    /// Limits which are unset in `resources` are left unchanged.
+    pub fn resource_limits(mut self, resources: models::LinuxResources) -> Self {
+        if let Ok(serde_json::Value::Object(mut fields)) = serde_json::to_value(resources) {
+            for key in RESOURCE_FIELDS {
+                match fields.remove(*key) {
+                    Some(serde_json::Value::Null) | None => {}
+                    Some(value) => {
+                        self.params.insert(key, value);
+                    }
+                }
+            }
+        }
+        self
+    }
+
+    impl_field!(
+        /// Block IO weights and throttling of the container.
+        block_io: models::LinuxBlockIo => "blockIO"
+    );
+
+    impl_field!(
+        /// CPU shares, quota and the CPUs the container may run on.
+        cpu: models::LinuxCpu => "cpu"
+    );
+
+    impl_vec_field!(
+        /// Device cgroup rules of the container.
+        devices: models::LinuxDeviceCgroup => "devices"
+    );
+
+    impl_vec_field!(
+        /// Hugetlb limits in bytes of the container.
+        hugepage_limits: models::LinuxHugepageLimit => "hugepageLimits"
+    );
+
+    impl_field!(
+        /// Memory and swap limits of the container.
+        memory: models::LinuxMemory => "memory"
+    );
+
+    impl_field!(
+        /// Network class id and priorities of the container.
+        network: models::LinuxNetwork => "network"
+    );
+
+    impl_field!(
+        /// Maximum number of processes of the container.
+        pids: models::LinuxPids => "pids"
+    );
+
+    impl_map_field!(json
+        /// Cgroup v2 parameters of the container.
+        unified => "unified"
+    );
+
+    impl_str_enum_field!(
+        /// Restart policy of the container. Requires libpod API 5.0 or newer.
+        restart_policy: ContainerRestartPolicy => "restartPolicy"
+    );
+
+    impl_field!(
+        /// Number of times the container is restarted when
+        /// [`restart_policy`](ContainerUpdateOptsBuilder::restart_policy) is set to `on-failure`.
+        /// Requires libpod API 5.0 or newer.
+        restart_retries: u64 => "restartRetries"
+    );
+
+    impl_str_field!(
+        /// Command run to check the health of the container. Requires libpod API 5.2 or newer.
+        health_cmd => "health_cmd"
+    );
+
+    impl_str_field!(
+        /// Time between health checks like `30s`, `disable` turns off the timer. Requires libpod
+        /// API 5.2 or newer.
+        health_interval => "health_interval"
+    );
+
+    impl_str_enum_field!(
+        /// Action taken when the container turns unhealthy. Requires libpod API 5.2 or newer.
+        health_on_failure: HealthCheckOnFailureAction => "health_on_failure"
+    );
+
+    impl_field!(
+        /// Number of failed health checks before the container is unhealthy. Requires libpod API
+        /// 5.2 or newer.
+        health_retries: u64 => "health_retries"
+    );
+
+    impl_str_field!(
+        /// Initialization time of the container before failed health checks are counted like
+        /// `1m`. Requires libpod API 5.2 or newer.
+        health_start_period => "health_start_period"
+    );
+
+    impl_str_field!(
+        /// Maximum time a health check may take like `30s`. Requires libpod API 5.2 or newer.
+        health_timeout => "health_timeout"
+    );
+
+    impl_field!(
+        /// Disable the health check of the container. Requires libpod API 5.2 or newer.
+        no_healthcheck: bool => "no_healthcheck"
+    );
+}
+
+#[derive(Debug, Default, Clone)]
+/// Adjust how paths are copied with
+/// [`Container::copy_path_to_container`](crate::api::Container::copy_path_to_container) and
+/// [`Container::copy_path_from_container`](crate::api::Container::copy_path_from_container).
+pub struct ContainerCopyOpts {
+    follow_symlinks: bool,
+    overwrite: bool,
+    preserve_ownership: bool,
+}
+
+impl ContainerCopyOpts {
+    /// Returns a new instance of a builder for ContainerCopyOpts.
+    pub fn builder() -> ContainerCopyOptsBuilder {
+        ContainerCopyOptsBuilder::default()
+    }
+
+    pub(crate) fn follow_symlinks(&self) -> bool {
+        self.follow_symlinks
+    }
+
+    pub(crate) fn overwrite(&self) -> bool {
+        self.overwrite
+    }
+
+    pub(crate) fn preserve_ownership(&self) -> bool {
+        self.preserve_ownership
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+/// A builder struct for [`ContainerCopyOpts`](ContainerCopyOpts).
+pub struct ContainerCopyOptsBuilder {
+    opts: ContainerCopyOpts,
+}
+
+impl ContainerCopyOptsBuilder {
+    /// Copy what a source path that is a symlink points to instead of the symlink itself.
+    /// Symlinks inside of copied directories are followed on the host only.
+    pub fn follow_symlinks(mut self, follow_symlinks: bool) -> Self {
+        self.opts.follow_symlinks = follow_symlinks;
+        self
+    }
+
+    /// Allow directories to be replaced by files and the other way around.
+    pub fn overwrite(mut self, overwrite: bool) -> Self {
+        self.opts.overwrite = overwrite;
+        self
+    }
+
+    /// Keep the uid and gid of the copied files. Otherwise files copied into a container are
+    /// owned by its main user and files copied out of it by the current user like with
+    /// `podman cp`. Keeping the ownership on the host usually requires root privileges.
+    pub fn preserve_ownership(mut self, preserve_ownership: bool) -> Self {
+        self.opts.preserve_ownership = preserve_ownership;
+        self
+    }
+
+    /// Finish building ContainerCopyOpts.
+    pub fn build(self) -> ContainerCopyOpts {
+        self.opts
+    }
+}
+
 impl_opts_builder!(url =>
     /// Adjust how to attach to a running container.
     ContainerAttach
@@ -1071,6 +1546,19 @@ impl ContainerAttachOpts {
         new.params.insert("stream", true.to_string());
         new
     }
+
+    /// Attaches to all streams unless explicitly disabled.
+    pub(crate) fn interactive(&self) -> Self {
+        let mut new = self.clone();
+        for stream in ["stdin", "stdout", "stderr"] {
+            new.params.entry(stream).or_insert_with(|| true.to_string());
+        }
+        new
+    }
+
+    pub(crate) fn detach_keys(&self) -> Option<&str> {
+        self.params.get("detachKeys").map(String::as_str)
+    }
 }
 
 impl ContainerAttachOptsBuilder {
@@ -1248,6 +1736,14 @@ impl_opts_builder!(url =>
     ContainerRestore
 );
 
+impl ContainerRestoreOpts {
+    pub(crate) fn for_import(&self) -> Self {
+        let mut new = self.clone();
+        new.params.insert("import", true.to_string());
+        new
+    }
+}
+
 impl ContainerRestoreOptsBuilder {
     impl_url_bool_field!(
         /// Do not include root file-system changes when exporting.
@@ -1299,3 +1795,37 @@ impl ContainerRestoreOptsBuilder {
         tcp_established => "tcpEstablished"
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{ContainerRestartPolicy, ContainerUpdateOpts};
+    use crate::ApiVersion;
+
+    #[test]
+    fn splits_update_query_and_body() {
+        let opts = ContainerUpdateOpts::builder()
+            .resource_limits(serde_json::from_str(r#"{"pids":{"limit":64}}"#).unwrap())
+            .build();
+        assert_eq!(opts.query(), None);
+        assert_eq!(opts.body().unwrap(), br#"{"pids":{"limit":64}}"#);
+        assert_eq!(
+            opts.required_api_version(),
+            ApiVersion::new(4, Some(3), None)
+        );
+
+        let opts = ContainerUpdateOpts::builder()
+            .restart_policy(ContainerRestartPolicy::OnFailure)
+            .restart_retries(3)
+            .health_cmd("true")
+            .build();
+        assert_eq!(
+            opts.query().as_deref(),
+            Some("restartPolicy=on-failure&restartRetries=3")
+        );
+        assert_eq!(opts.body().unwrap(), br#"{"health_cmd":"true"}"#);
+        assert_eq!(
+            opts.required_api_version(),
+            ApiVersion::new(5, Some(2), None)
+        );
+    }
+}
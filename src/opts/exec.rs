@@ -1,7 +1,9 @@
 use containers_api::{
     impl_field, impl_opts_builder, impl_str_enum_field, impl_str_field, impl_vec_field,
 };
+use std::collections::BTreeMap;
 use std::fmt;
+use std::time::Duration;
 
 impl_opts_builder!(json =>
     /// Modify how an exec session is run inside a container.
@@ -117,3 +119,96 @@ impl ExecStartOptsBuilder {
         width: usize => "w"
     );
 }
+
+#[derive(Debug, Clone, Default)]
+/// Adjust how a command is run by
+/// [`Container::exec_output`](crate::api::Container::exec_output).
+pub struct ExecOutputOpts {
+    pub(crate) params: BTreeMap<&'static str, serde_json::Value>,
+    pub(crate) stdin: Option<Vec<u8>>,
+    pub(crate) output_limit: Option<usize>,
+    pub(crate) timeout: Option<Duration>,
+}
+
+impl ExecOutputOpts {
+    /// Returns a new instance of a builder for ExecOutputOpts.
+    pub fn builder() -> ExecOutputOptsBuilder {
+        ExecOutputOptsBuilder::default()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// A builder struct for ExecOutputOpts.
+pub struct ExecOutputOptsBuilder {
+    params: BTreeMap<&'static str, serde_json::Value>,
+    stdin: Option<Vec<u8>>,
+    output_limit: Option<usize>,
+    timeout: Option<Duration>,
+}
+
+impl ExecOutputOptsBuilder {
+    /// A list of environment variables to use for the command execution.
+    pub fn env<K, V>(mut self, vars: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        self.params.insert(
+            "Env",
+            vars.into_iter()
+                .map(|(k, v)| format!("{}={}", k.as_ref(), v.as_ref()))
+                .collect(),
+        );
+        self
+    }
+
+    impl_field!(
+        /// Runs the exec process with extended privileges
+        privileged: bool => "Privileged"
+    );
+
+    impl_field!(
+        /// Allocate a pseudo-TTY. The output of the command is then returned as stdout.
+        tty: bool => "Tty"
+    );
+
+    impl_str_enum_field!(
+        /// The user, and optionally, group to run the exec process inside the container.
+        user: UserOpt => "User"
+    );
+
+    impl_str_field!(
+        /// The working directory for the exec process inside the container.
+        working_dir => "WorkingDir"
+    );
+
+    /// Bytes written to the stdin of the command. Stdin is closed once all bytes are written.
+    pub fn stdin(mut self, input: impl Into<Vec<u8>>) -> Self {
+        self.stdin = Some(input.into());
+        self
+    }
+
+    /// Maximum number of bytes of stdout and stderr kept in memory. Any output above the limit
+    /// is discarded and the output is marked as truncated.
+    pub fn output_limit(mut self, limit: usize) -> Self {
+        self.output_limit = Some(limit);
+        self
+    }
+
+    /// Maximum time to wait for the command to finish. The command is not killed when the
+    /// timeout elapses.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Finish building ExecOutputOpts.
+    pub fn build(self) -> ExecOutputOpts {
+        ExecOutputOpts {
+            params: self.params,
+            stdin: self.stdin,
+            output_limit: self.output_limit,
+            timeout: self.timeout,
+        }
+    }
+}
@@ -1,22 +1,105 @@
 use containers_api::opts::{Filter, FilterItem};
 use containers_api::{
-    impl_filter_func, impl_map_field, impl_opts_builder, impl_opts_required_builder,
-    impl_url_bool_field, impl_url_enum_field, impl_url_field, impl_url_str_field,
-    impl_url_vec_field,
+    impl_filter_func, impl_map_field, impl_opts_builder, impl_url_bool_field, impl_url_enum_field,
+    impl_url_field, impl_url_str_field, impl_url_vec_field,
 };
+use crate::opts::ImageReference;
 use serde::Serialize;
 use base64::engine::Engine;
 use std::collections::HashMap;
 use std::fmt;
 
-impl_opts_required_builder!(url =>
-    /// Adjust how an image is built.
-    ImageBuild,
+#[derive(Serialize, Debug, Default, Clone)]
+/// Adjust how an image is built.
+pub struct ImageBuildOpts {
+    pub(crate) params: std::collections::BTreeMap<&'static str, String>,
+    pub(crate) vec_params: std::collections::BTreeMap<&'static str, Vec<String>>,
+    path: String,
+    /// Only used to pack a local build context, never sent to podman.
+    #[serde(skip)]
+    ignore_file: Option<std::path::PathBuf>,
+}
+
+impl ImageBuildOpts {
+    /// Returns a new instance of a builder for ImageBuildOpts.
     ///
     /// Parameters:
     /// * path - Path to a build context directory
-    path => "path"
-);
+    pub fn builder(path: impl Into<String>) -> ImageBuildOptsBuilder {
+        ImageBuildOptsBuilder::new(path)
+    }
+
+    /// Returns the value of the URL parameter `key`.
+    pub fn get_param(&self, key: &str) -> Option<&String> {
+        self.params.get(key)
+    }
+
+    /// Path to the build context directory.
+    pub fn path(&self) -> &String {
+        &self.path
+    }
+
+    /// Serialize options as a URL query String. Returns None if no options are defined.
+    pub fn serialize(&self) -> Option<String> {
+        let mut serialized = containers_api::url::encoded_pairs(&self.params);
+        let vec_p = containers_api::url::encoded_vec_pairs(&self.vec_params);
+
+        if !vec_p.is_empty() {
+            if !serialized.is_empty() {
+                serialized.push('&');
+            }
+            serialized.push_str(&vec_p);
+        }
+
+        if serialized.is_empty() {
+            None
+        } else {
+            Some(serialized)
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A builder struct for ImageBuildOpts.
+pub struct ImageBuildOptsBuilder {
+    pub(crate) params: std::collections::BTreeMap<&'static str, String>,
+    pub(crate) vec_params: std::collections::BTreeMap<&'static str, Vec<String>>,
+    path: String,
+    ignore_file: Option<std::path::PathBuf>,
+}
+
+impl ImageBuildOptsBuilder {
+    /// A builder struct for ImageBuildOpts.
+    ///
+    /// Parameters:
+    /// * path - Path to a build context directory
+    pub fn new(path: impl Into<String>) -> Self {
+        let path = path.into();
+        Self {
+            params: [("path", path.clone())].into(),
+            vec_params: Default::default(),
+            path,
+            ignore_file: None,
+        }
+    }
+
+    /// Path to an alternate `.containerignore` file used to exclude files from a local build
+    /// context directory, the same as `--ignorefile` of `podman build`.
+    pub fn ignore_file(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.ignore_file = Some(path.into());
+        self
+    }
+
+    /// Finish building ImageBuildOpts.
+    pub fn build(self) -> ImageBuildOpts {
+        ImageBuildOpts {
+            params: self.params,
+            vec_params: self.vec_params,
+            path: self.path,
+            ignore_file: self.ignore_file,
+        }
+    }
+}
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 /// The networking mode for the run commands during image build.
@@ -79,6 +162,19 @@ impl Platform {
         self.version = Some(version.into());
         self
     }
+
+    pub(crate) fn os_name(&self) -> &str {
+        &self.os
+    }
+
+    pub(crate) fn arch_name(&self) -> Option<&str> {
+        self.arch.as_deref()
+    }
+
+    /// The variant of the architecture like `v7` in `linux/arm/v7`.
+    pub(crate) fn variant_name(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
 }
 
 impl fmt::Display for Platform {
@@ -95,6 +191,36 @@ impl fmt::Display for Platform {
     }
 }
 
+impl ImageBuildOpts {
+    /// Returns a new instance of a builder for ImageBuildOpts used with
+    /// [`Images::build_with_context`](crate::api::Images::build_with_context) where no path to
+    /// the build context is needed.
+    pub fn context_builder() -> ImageBuildOptsBuilder {
+        let mut builder = ImageBuildOptsBuilder::new("");
+        builder.params.remove("path");
+        builder
+    }
+
+    /// These options with the target platform of the build set to `platform`.
+    pub(crate) fn for_platform(&self, platform: &Platform) -> Self {
+        let mut opts = self.clone();
+        opts.params.insert("platform", platform.to_string());
+        opts
+    }
+
+    pub(crate) fn ignore_file(&self) -> Option<std::path::PathBuf> {
+        self.ignore_file.clone()
+    }
+
+    /// Dockerfiles the build may use which must be a part of the build context.
+    pub(crate) fn dockerfiles(&self) -> Vec<String> {
+        match self.params.get("dockerfile") {
+            Some(dockerfile) => vec![dockerfile.clone()],
+            None => vec!["Containerfile".into(), "Dockerfile".into()],
+        }
+    }
+}
+
 impl ImageBuildOptsBuilder {
     impl_url_bool_field!(
         /// Instead of building for a set of platforms specified using the platform option,
@@ -348,6 +474,13 @@ impl ImageTagOptsBuilder {
         /// Set the image tag.
         tag => "tag"
     );
+
+    /// Set the repository and tag from `reference`, the tag defaults to `latest`.
+    pub fn reference(self, reference: &ImageReference) -> Self {
+        let reference = reference.with_default_tag();
+        self.repo(reference.name())
+            .tag(reference.tag().unwrap_or_default())
+    }
 }
 
 #[derive(Clone, Serialize, Debug)]
@@ -462,6 +595,14 @@ impl PullOpts {
     pub(crate) fn auth_header(&self) -> Option<String> {
         self.auth.clone().map(|a| a.serialize())
     }
+
+    /// The reference of the pulled image, unless credentials were passed as a parameter.
+    pub(crate) fn reference_without_credentials(&self) -> Option<&str> {
+        if self.params.contains_key("credentials") {
+            return None;
+        }
+        self.params.get("reference").map(String::as_str)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -562,18 +703,69 @@ impl_opts_builder!(url =>
     ImageExport
 );
 
+impl ImageExportOpts {
+    /// Whether the image is exported as a directory, sent by podman as a tar archive of it.
+    pub(crate) fn exports_dir(&self) -> bool {
+        ImageExportFormat::is_dir_param(self.params.get("format"))
+    }
+}
+
 impl ImageExportOptsBuilder {
     impl_url_bool_field!(
         /// Use compression on image.
         compress => "compress"
     );
 
-    impl_url_str_field!(
+    impl_url_enum_field!(
         /// Format for exported image.
-        format => "format"
+        format: ImageExportFormat => "format"
     );
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// The format in which an image is exported.
+pub enum ImageExportFormat {
+    /// A tar archive in the format of `docker save`, the default.
+    #[default]
+    DockerArchive,
+    /// A tar archive with an OCI image layout.
+    OciArchive,
+    /// A directory with an OCI image layout.
+    OciDir,
+    /// A directory with the manifest and layers of the image in the docker format.
+    DockerDir,
+}
+
+impl ImageExportFormat {
+    /// Whether the format is a directory rather than an archive.
+    pub fn is_dir(&self) -> bool {
+        matches!(self, Self::OciDir | Self::DockerDir)
+    }
+
+    fn is_dir_param(format: Option<&String>) -> bool {
+        [Self::OciDir, Self::DockerDir]
+            .iter()
+            .any(|dir| format.is_some_and(|format| format == dir.as_ref()))
+    }
+}
+
+impl AsRef<str> for ImageExportFormat {
+    fn as_ref(&self) -> &str {
+        match self {
+            ImageExportFormat::DockerArchive => "docker-archive",
+            ImageExportFormat::OciArchive => "oci-archive",
+            ImageExportFormat::OciDir => "oci-dir",
+            ImageExportFormat::DockerDir => "docker-dir",
+        }
+    }
+}
+
+impl fmt::Display for ImageExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_ref())
+    }
+}
+
 impl_opts_builder!(url =>
     /// Adjust how an image is imported.
     ImageImport
@@ -672,6 +864,10 @@ impl ImagePushOpts {
     pub(crate) fn auth_header(&self) -> Option<String> {
         self.auth.clone().map(|a| a.serialize())
     }
+
+    pub(crate) fn destination(&self) -> Option<&str> {
+        self.params.get("destination").map(String::as_str)
+    }
 }
 
 #[derive(Debug, Default)]
@@ -792,6 +988,17 @@ impl_opts_builder!(url =>
     ImageSearch
 );
 
+impl ImageSearchOpts {
+    /// The search term if it is prefixed with the registry to search, like `quay.io/podman`.
+    pub(crate) fn registry_term(&self) -> Option<&str> {
+        self.params.get("term").map(String::as_str).filter(|term| {
+            term.split_once('/').is_some_and(|(registry, _)| {
+                registry.contains('.') || registry.contains(':') || registry == "localhost"
+            })
+        })
+    }
+}
+
 impl ImageSearchOptsBuilder {
     impl_filter_func!(
         /// Filters to process on the images list.
@@ -824,15 +1031,22 @@ impl_opts_builder!(url =>
     ImagesExport
 );
 
+impl ImagesExportOpts {
+    pub(crate) fn exports_dir(&self) -> bool {
+        ImageExportFormat::is_dir_param(self.params.get("format"))
+    }
+}
+
 impl ImagesExportOptsBuilder {
     impl_url_bool_field!(
         /// Use compression on image.
         compress => "compress"
     );
 
-    impl_url_str_field!(
-        /// Format for exported image (only docker-archive is supported).
-        format => "format"
+    impl_url_enum_field!(
+        /// Format for exported images, podman only supports exporting multiple images as
+        /// [`ImageExportFormat::DockerArchive`](ImageExportFormat::DockerArchive).
+        format: ImageExportFormat => "format"
     );
 
     impl_url_bool_field!(
@@ -3,6 +3,8 @@ use containers_api::{
     impl_url_vec_field, impl_vec_field,
 };
 
+use crate::opts::{ImageBuildOpts, Platform};
+
 impl_opts_required_builder!(url =>
     /// Adjust how a manifest list is created.
     ManifestCreate,
@@ -104,3 +106,117 @@ impl ManifestPushOptsBuilder {
         tls_verify => "tlsVerify"
     );
 }
+
+#[derive(Debug, Clone)]
+/// Adjust how an image is built for multiple platforms into a manifest list with
+/// [`Manifests::build`](crate::api::Manifests::build).
+pub struct ManifestBuildOpts {
+    name: String,
+    platforms: Vec<Platform>,
+    build: ImageBuildOpts,
+    parallel: bool,
+    amend: bool,
+    push: Option<ManifestPushOpts>,
+}
+
+impl ManifestBuildOpts {
+    /// Returns a new instance of a builder for ManifestBuildOpts creating the manifest list
+    /// `name`.
+    pub fn builder(name: impl Into<String>) -> ManifestBuildOptsBuilder {
+        ManifestBuildOptsBuilder::new(name)
+    }
+
+    pub(crate) fn platforms(&self) -> &[Platform] {
+        &self.platforms
+    }
+
+    pub(crate) fn build_opts(&self, platform: &Platform) -> ImageBuildOpts {
+        self.build.for_platform(platform)
+    }
+
+    pub(crate) fn is_parallel(&self) -> bool {
+        self.parallel
+    }
+
+    pub(crate) fn create_opts(&self) -> ManifestCreateOpts {
+        ManifestCreateOpts::builder(&self.name)
+            .amend(self.amend)
+            .build()
+    }
+
+    pub(crate) fn push_opts(&self) -> Option<&ManifestPushOpts> {
+        self.push.as_ref()
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A builder struct for [`ManifestBuildOpts`](ManifestBuildOpts).
+pub struct ManifestBuildOptsBuilder {
+    name: String,
+    platforms: Vec<Platform>,
+    build: Option<ImageBuildOpts>,
+    parallel: bool,
+    amend: bool,
+    push: Option<ManifestPushOpts>,
+}
+
+impl ManifestBuildOptsBuilder {
+    /// Returns a new instance of a builder for the manifest list `name`.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            platforms: vec![],
+            build: None,
+            parallel: false,
+            amend: false,
+            push: None,
+        }
+    }
+
+    /// The platforms to build the image for, one image is built for each of them.
+    pub fn platforms(mut self, platforms: impl IntoIterator<Item = Platform>) -> Self {
+        self.platforms.extend(platforms);
+        self
+    }
+
+    /// Options used for the build of every platform, their platform is overridden. Tags set here
+    /// are applied to the image of every platform so they are usually left out, the images are
+    /// added to the list by id. Defaults to
+    /// [`ImageBuildOpts::context_builder`](ImageBuildOpts::context_builder) without any options.
+    pub fn build_opts(mut self, opts: ImageBuildOpts) -> Self {
+        self.build = Some(opts);
+        self
+    }
+
+    /// Build the platforms concurrently instead of one after another.
+    pub fn parallel(mut self, parallel: bool) -> Self {
+        self.parallel = parallel;
+        self
+    }
+
+    /// Add the images to an existing list with the same name instead of failing.
+    pub fn amend(mut self, amend: bool) -> Self {
+        self.amend = amend;
+        self
+    }
+
+    /// Push the manifest list once all images were added to it.
+    pub fn push(mut self, opts: ManifestPushOpts) -> Self {
+        self.push = Some(opts);
+        self
+    }
+
+    /// Finish building ManifestBuildOpts.
+    pub fn build(self) -> ManifestBuildOpts {
+        ManifestBuildOpts {
+            name: self.name,
+            platforms: self.platforms,
+            build: self
+                .build
+                .unwrap_or_else(|| ImageBuildOpts::context_builder().build()),
+            parallel: self.parallel,
+            amend: self.amend,
+            push: self.push,
+        }
+    }
+}
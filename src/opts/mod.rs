@@ -1,29 +1,47 @@
 //! Options used for configuring the behavior of certain API endpoints
 
+mod build;
 mod containers;
 mod exec;
 mod images;
 mod manifests;
 mod networks;
 mod pods;
+mod reference;
 mod volumes;
 
+pub use build::*;
 pub use containers::*;
 pub use exec::*;
 pub use images::*;
 pub use manifests::*;
 pub use networks::*;
 pub use pods::*;
+pub use reference::*;
 pub use volumes::*;
 
 pub type EventsConstraint = (String, Vec<String>);
 
+use crate::ApiVersion;
 use containers_api::{
     impl_opts_builder, impl_opts_required_builder, impl_url_bool_field, impl_url_enum_field,
     impl_url_field, impl_url_str_field, impl_url_vec_field,
 };
+use std::collections::BTreeMap;
 use std::fmt;
 
+/// Returns the highest of the API versions in `requirements` whose parameter is set in `params`.
+pub(crate) fn required_api_version<V>(
+    params: &BTreeMap<&'static str, V>,
+    requirements: &[(&str, ApiVersion)],
+) -> Option<ApiVersion> {
+    requirements
+        .iter()
+        .filter(|(param, _)| params.contains_key(param))
+        .map(|(_, version)| *version)
+        .max()
+}
+
 impl_opts_builder!(
     url =>
     /// Used to filter events returned by [Podman::events](crate::Podman::events).
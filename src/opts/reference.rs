@@ -0,0 +1,457 @@
+use crate::{Error, Id, Result};
+
+use std::{fmt, str::FromStr};
+
+/// Registry of references without a registry like `alpine`.
+const DEFAULT_DOMAIN: &str = "docker.io";
+/// Legacy name of the default registry normalized to [`DEFAULT_DOMAIN`].
+const LEGACY_DEFAULT_DOMAIN: &str = "index.docker.io";
+/// Namespace of official images on the default registry.
+const OFFICIAL_REPO_PREFIX: &str = "library/";
+/// Tag used by podman when a reference has neither a tag nor a digest.
+const DEFAULT_TAG: &str = "latest";
+
+const NAME_MAX_LENGTH: usize = 255;
+const TAG_MAX_LENGTH: usize = 128;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// A parsed and normalized image reference like `quay.io/podman/hello:latest`.
+///
+/// References are validated with the same grammar as
+/// [containers/image](https://github.com/containers/image/tree/main/docker/reference). Short names
+/// are normalized to the default registry, so `alpine` becomes `docker.io/library/alpine`.
+///
+/// A reference can be passed anywhere the API takes the name of an image, for example:
+///
+/// ```no_run
+/// async {
+///     use podman_api::opts::{ImageReference, ImageTagOpts};
+///     use podman_api::Podman;
+///
+///     let podman = Podman::unix("/run/user/1000/podman/podman.sock");
+///
+///     let reference: ImageReference = "alpine:3.19".parse().unwrap();
+///     assert_eq!(reference.to_string(), "docker.io/library/alpine:3.19");
+///
+///     let image = podman.images().get(&reference);
+///     let target = reference.with_tag("stable").unwrap();
+///     if let Err(e) = image
+///         .tag(&ImageTagOpts::builder().reference(&target).build())
+///         .await
+///     {
+///         eprintln!("{}", e);
+///     }
+/// };
+/// ```
+pub struct ImageReference {
+    domain: String,
+    path: String,
+    tag: Option<String>,
+    digest: Option<String>,
+}
+
+impl ImageReference {
+    /// Parses and normalizes `reference`. Fails if it isn't a valid reference, for example when
+    /// the repository contains uppercase characters or the reference is a 64 character image ID.
+    pub fn parse(reference: impl AsRef<str>) -> Result<Self> {
+        let reference = reference.as_ref();
+        let invalid = |message: &str| Error::InvalidReference {
+            reference: reference.to_string(),
+            message: message.to_string(),
+        };
+        if reference.is_empty() {
+            return Err(invalid("reference is empty"));
+        }
+
+        let (rest, digest) = match reference.split_once('@') {
+            Some((rest, digest)) => {
+                if !is_digest(digest) {
+                    return Err(invalid("invalid digest"));
+                }
+                (rest, Some(digest.to_string()))
+            }
+            None => (reference, None),
+        };
+        // a colon after the last slash separates the tag, others belong to a registry port
+        let (name, tag) = match rest.rfind(':') {
+            Some(i) if !rest[i..].contains('/') => (&rest[..i], Some(&rest[i + 1..])),
+            _ => (rest, None),
+        };
+        if let Some(tag) = tag
+            && !is_tag(tag)
+        {
+            return Err(invalid("invalid tag"));
+        }
+        if name.len() > NAME_MAX_LENGTH {
+            return Err(invalid(
+                "repository name must not be longer than 255 characters",
+            ));
+        }
+
+        let (domain, path) = split_domain(name);
+        if path.len() == 64 && path.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(invalid(
+                "cannot specify 64-byte hexadecimal strings, use an image ID instead",
+            ));
+        }
+        if !is_domain(domain) {
+            return Err(invalid("invalid registry"));
+        }
+        if path.to_lowercase() != path {
+            return Err(invalid("repository name must be lowercase"));
+        }
+        if path.is_empty() || !path.split('/').all(is_path_component) {
+            return Err(invalid("invalid repository name"));
+        }
+
+        let domain = match domain {
+            LEGACY_DEFAULT_DOMAIN => DEFAULT_DOMAIN,
+            domain => domain,
+        };
+        let path = if domain == DEFAULT_DOMAIN && !path.contains('/') {
+            format!("{OFFICIAL_REPO_PREFIX}{path}")
+        } else {
+            path.to_string()
+        };
+        Ok(Self {
+            domain: domain.to_string(),
+            path,
+            tag: tag.map(str::to_string),
+            digest,
+        })
+    }
+
+    /// The registry of the image like `docker.io` or `localhost:5000`.
+    pub fn registry(&self) -> &str {
+        &self.domain
+    }
+
+    /// The path of the repository within the registry like `library/alpine`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The full name of the repository including the registry like `docker.io/library/alpine`.
+    pub fn name(&self) -> String {
+        format!("{}/{}", self.domain, self.path)
+    }
+
+    /// The tag of the image if it was specified.
+    pub fn tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+
+    /// The digest pinning the image like `sha256:...` if it was specified.
+    pub fn digest(&self) -> Option<&str> {
+        self.digest.as_deref()
+    }
+
+    /// Whether the reference is pinned to a digest.
+    pub fn is_digested(&self) -> bool {
+        self.digest.is_some()
+    }
+
+    /// Returns a copy of this reference with the given tag.
+    pub fn with_tag(&self, tag: impl Into<String>) -> Result<Self> {
+        let tag = tag.into();
+        if !is_tag(&tag) {
+            return Err(Error::InvalidReference {
+                reference: format!("{}:{tag}", self.name()),
+                message: "invalid tag".into(),
+            });
+        }
+        Ok(Self {
+            tag: Some(tag),
+            ..self.clone()
+        })
+    }
+
+    /// Returns a copy of this reference pinned to the given digest like `sha256:...`.
+    pub fn with_digest(&self, digest: impl Into<String>) -> Result<Self> {
+        let digest = digest.into();
+        if !is_digest(&digest) {
+            return Err(Error::InvalidReference {
+                reference: format!("{}@{digest}", self.name()),
+                message: "invalid digest".into(),
+            });
+        }
+        Ok(Self {
+            digest: Some(digest),
+            ..self.clone()
+        })
+    }
+
+    /// Returns a copy of this reference with the `latest` tag if it has neither a tag nor a
+    /// digest, which is what podman resolves it to.
+    pub fn with_default_tag(&self) -> Self {
+        let mut reference = self.clone();
+        if reference.tag.is_none() && reference.digest.is_none() {
+            reference.tag = Some(DEFAULT_TAG.into());
+        }
+        reference
+    }
+}
+
+impl fmt::Display for ImageReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.domain, self.path)?;
+        if let Some(tag) = &self.tag {
+            write!(f, ":{tag}")?;
+        }
+        if let Some(digest) = &self.digest {
+            write!(f, "@{digest}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ImageReference {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+impl TryFrom<&str> for ImageReference {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self> {
+        Self::parse(value)
+    }
+}
+
+impl TryFrom<String> for ImageReference {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        Self::parse(value)
+    }
+}
+
+impl From<ImageReference> for String {
+    fn from(reference: ImageReference) -> Self {
+        reference.to_string()
+    }
+}
+
+impl From<&ImageReference> for String {
+    fn from(reference: &ImageReference) -> Self {
+        reference.to_string()
+    }
+}
+
+impl From<ImageReference> for Id {
+    fn from(reference: ImageReference) -> Self {
+        reference.to_string().into()
+    }
+}
+
+impl From<&ImageReference> for Id {
+    fn from(reference: &ImageReference) -> Self {
+        reference.to_string().into()
+    }
+}
+
+/// Splits the registry from a name, references without one use the default registry.
+fn split_domain(name: &str) -> (&str, &str) {
+    match name.split_once('/') {
+        Some((first, rest))
+            if first.contains(['.', ':'])
+                || first == "localhost"
+                || first.to_lowercase() != first =>
+        {
+            (first, rest)
+        }
+        _ => (DEFAULT_DOMAIN, name),
+    }
+}
+
+/// `host[:port]` where host is a dot separated list of alphanumeric labels that may contain
+/// hyphens, or an IPv6 address in brackets.
+fn is_domain(domain: &str) -> bool {
+    let (host, port) = match domain.strip_prefix('[') {
+        Some(rest) => {
+            let Some((ip, port)) = rest.split_once(']') else {
+                return false;
+            };
+            if ip.parse::<std::net::Ipv6Addr>().is_err() {
+                return false;
+            }
+            (None, port)
+        }
+        None => match domain.find(':') {
+            Some(i) => (Some(&domain[..i]), &domain[i..]),
+            None => (Some(domain), ""),
+        },
+    };
+    let valid_host = host.is_none_or(|host| {
+        host.split('.').all(|label| {
+            !label.is_empty()
+                && label
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'-')
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+        })
+    });
+    let valid_port = port.is_empty()
+        || port
+            .strip_prefix(':')
+            .is_some_and(|port| !port.is_empty() && port.bytes().all(|b| b.is_ascii_digit()));
+    valid_host && valid_port
+}
+
+/// `[a-z0-9]+(?:(?:[._]|__|[-]*)[a-z0-9]+)*`
+fn is_path_component(component: &str) -> bool {
+    let bytes = component.as_bytes();
+    let alnum = |b: &u8| b.is_ascii_lowercase() || b.is_ascii_digit();
+    if !bytes.first().is_some_and(alnum) || !bytes.last().is_some_and(alnum) {
+        return false;
+    }
+    let mut i = 0;
+    while i < bytes.len() {
+        if alnum(&bytes[i]) {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < bytes.len() && !alnum(&bytes[i]) {
+            i += 1;
+        }
+        match &component[start..i] {
+            "." | "_" | "__" => {}
+            separator if separator.bytes().all(|b| b == b'-') => {}
+            _ => return false,
+        }
+    }
+    true
+}
+
+/// `[\w][\w.-]{0,127}`
+fn is_tag(tag: &str) -> bool {
+    let word = |b: u8| b.is_ascii_alphanumeric() || b == b'_';
+    tag.len() <= TAG_MAX_LENGTH
+        && tag.bytes().next().is_some_and(word)
+        && tag.bytes().all(|b| word(b) || b == b'.' || b == b'-')
+}
+
+/// `algorithm:hex` where the algorithm is made of alphanumeric components separated by `-_+.`
+/// and the hex part is at least 32 characters long, exactly 64 for `sha256`.
+fn is_digest(digest: &str) -> bool {
+    let Some((algorithm, hex)) = digest.split_once(':') else {
+        return false;
+    };
+    let valid_algorithm = algorithm.split(['-', '_', '+', '.']).all(|component| {
+        component
+            .bytes()
+            .next()
+            .is_some_and(|b| b.is_ascii_alphabetic())
+            && component.bytes().all(|b| b.is_ascii_alphanumeric())
+    });
+    let valid_hex = hex.len() >= 32 && hex.bytes().all(|b| b.is_ascii_hexdigit());
+    valid_algorithm && valid_hex && (algorithm != "sha256" || hex.len() == 64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ImageReference;
+
+    const DIGEST: &str = "sha256:e692418e4cbaf90ca69d05a66403747baa33ee08806650b51fab815ad7fc331f";
+
+    #[test]
+    fn parses_and_normalizes_references() {
+        let cases = [
+            ("alpine", "docker.io", "library/alpine", None, false),
+            (
+                "alpine:3.19",
+                "docker.io",
+                "library/alpine",
+                Some("3.19"),
+                false,
+            ),
+            ("user/app", "docker.io", "user/app", None, false),
+            (
+                "index.docker.io/user/app",
+                "docker.io",
+                "user/app",
+                None,
+                false,
+            ),
+            (
+                "quay.io/podman/hello:latest",
+                "quay.io",
+                "podman/hello",
+                Some("latest"),
+                false,
+            ),
+            ("localhost/app", "localhost", "app", None, false),
+            (
+                "localhost:5000/a/b-c__d.e:v1",
+                "localhost:5000",
+                "a/b-c__d.e",
+                Some("v1"),
+                false,
+            ),
+            ("[::1]:5000/app", "[::1]:5000", "app", None, false),
+            ("Registry/app", "Registry", "app", None, false),
+        ];
+        for (input, registry, path, tag, digested) in cases {
+            let reference = ImageReference::parse(input).unwrap();
+            assert_eq!(reference.registry(), registry, "{input}");
+            assert_eq!(reference.path(), path, "{input}");
+            assert_eq!(reference.tag(), tag, "{input}");
+            assert_eq!(reference.is_digested(), digested, "{input}");
+        }
+
+        let reference = ImageReference::parse(format!("alpine:3.19@{DIGEST}")).unwrap();
+        assert_eq!(reference.digest(), Some(DIGEST));
+        assert_eq!(
+            reference.to_string(),
+            format!("docker.io/library/alpine:3.19@{DIGEST}")
+        );
+        assert_eq!(
+            ImageReference::parse("alpine")
+                .unwrap()
+                .with_default_tag()
+                .to_string(),
+            "docker.io/library/alpine:latest"
+        );
+        assert_eq!(
+            ImageReference::parse("quay.io/podman/hello")
+                .unwrap()
+                .with_tag("v2")
+                .unwrap()
+                .to_string(),
+            "quay.io/podman/hello:v2"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_references() {
+        for input in [
+            "",
+            "Alpine",
+            "alpine:",
+            "alpine:-tag",
+            "alpine@sha256:1234",
+            "a//b",
+            "-app",
+            "app-",
+            "a..b",
+            "quay.io:port/app",
+            "-quay.io/app",
+            "e692418e4cbaf90ca69d05a66403747baa33ee08806650b51fab815ad7fc331f",
+            "quay.io/",
+        ] {
+            assert!(
+                ImageReference::parse(input).is_err(),
+                "{input} should be invalid"
+            );
+        }
+        assert!(ImageReference::parse("alpine")
+            .unwrap()
+            .with_tag("bad tag")
+            .is_err());
+    }
+}
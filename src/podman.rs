@@ -3,33 +3,73 @@
 use crate::{
     api::{self, ApiResource},
     conn::{self, get_http_connector, Headers, Payload, RequestClient, Transport},
+    credentials::CredentialStore,
     models,
     opts::*,
-    ApiVersion, Error, Result, Value, LATEST_API_VERSION,
+    ApiError, ApiVersion, Error, Result, Value, LATEST_API_VERSION,
 };
 
 #[cfg(feature = "tls")]
 use crate::conn::get_https_connector;
 #[cfg(unix)]
 use crate::conn::get_unix_connector;
+#[cfg(all(unix, feature = "ssh"))]
+use crate::ssh::{SshDestination, SshTunnel};
 
-use crate::conn::hyper::{Body, Client, Response};
+use crate::conn::hyper::{
+    self, client::HttpConnector, Body, Client, Request, Response, StatusCode,
+};
 use bytes::Bytes;
 use containers_api::url;
-use futures_util::{stream::Stream, AsyncRead, AsyncWrite, TryStreamExt};
+use futures_util::{stream::Stream, AsyncRead, AsyncWrite, StreamExt, TryFutureExt, TryStreamExt};
 use serde::de::DeserializeOwned;
 use std::future::Future;
 use std::path::Path;
 use std::pin::Pin;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+/// Header with the credentials of multiple registries used by builds.
+const REGISTRY_CONFIG_HEADER: &str = "X-Registry-Config";
 
 /// Entrypoint interface for communicating with podman daemon
 #[derive(Debug, Clone)]
 pub struct Podman {
     version: ApiVersion,
     pub(crate) client: RequestClient<Error>,
+    // Used directly for requests the client can't make, like GET requests with headers.
+    transport: Transport,
+    config: RequestConfig,
+    // Shared by all clones so that the server is only queried once.
+    capabilities: Arc<OnceLock<models::Capabilities>>,
+    #[cfg(all(unix, feature = "ssh"))]
+    // Keeps the forwarding `ssh` process alive for as long as any clone of this client exists.
+    _tunnel: Option<Arc<SshTunnel>>,
 }
 
 impl Podman {
+    /// Returns a builder of a Podman instance for the given `uri` that allows configuring request
+    /// timeouts and retries. The `uri` accepts the same schemes as [`Podman::new`](Podman::new).
+    ///
+    /// Example:
+    ///
+    /// ```no_run
+    /// use podman_api::credentials::CredentialStore;
+    /// use podman_api::{Podman, RetryPolicy};
+    /// use std::time::Duration;
+    ///
+    /// let podman = Podman::builder("unix:///run/user/1000/podman/podman.sock")
+    ///     .request_timeout(Duration::from_secs(30))
+    ///     .retry_policy(RetryPolicy::exponential(5))
+    ///     .stream_idle_timeout(Duration::from_secs(300))
+    ///     .registry_credentials(CredentialStore::from_env())
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn builder(uri: impl Into<String>) -> PodmanBuilder {
+        PodmanBuilder::new(uri)
+    }
+
     /// Creates a new Podman instance by automatically choosing appropriate connection type based
     /// on provided `uri`.
     ///
@@ -37,6 +77,11 @@ impl Podman {
     ///  - `unix://` only works when build target is `unix`, otherwise returns an Error
     ///  - `tcp://`
     ///  - `http://`
+    ///  - `ssh://` only works with `ssh` feature enabled on `unix` targets, see
+    ///    [`Podman::ssh`](Podman::ssh)
+    ///
+    ///  With an `ssh://` URI this call blocks the current thread while the tunnel is being
+    ///  established, see [`Podman::ssh`](Podman::ssh) for details.
     ///
     ///  To create a Podman instance utilizing TLS use explicit [Podman::tls](Podman::tls)
     ///  constructor (this requires `tls` feature enabled).
@@ -77,12 +122,26 @@ impl Podman {
                     Err(Error::MissingAuthority)
                 }
             }
+            #[cfg(all(unix, feature = "ssh"))]
+            Some("ssh") => Podman::ssh_versioned(uri, version, None::<&Path>),
             Some(scheme) => Err(Error::UnsupportedScheme(scheme.to_string())),
             None => unreachable!(), // This is never possible because calling split on an empty string
                                     // always returns at least one element
         }
     }
 
+    fn with_transport(version: impl Into<ApiVersion>, transport: Transport) -> Podman {
+        Podman {
+            version: version.into(),
+            client: RequestClient::new(transport.clone(), Box::new(validate_response)),
+            transport,
+            config: RequestConfig::default(),
+            capabilities: Default::default(),
+            #[cfg(all(unix, feature = "ssh"))]
+            _tunnel: None,
+        }
+    }
+
     #[cfg(unix)]
     #[cfg_attr(docsrs, doc(cfg(unix)))]
     /// Creates a new podman instance for a podman host listening on a given Unix socket.
@@ -106,18 +165,88 @@ impl Podman {
     where
         P: AsRef<Path>,
     {
-        Podman {
-            version: version.into(),
-            client: RequestClient::new(
-                Transport::Unix {
-                    client: Client::builder()
-                        .pool_max_idle_per_host(0)
-                        .build(get_unix_connector()),
-                    path: socket_path.as_ref().to_path_buf(),
-                },
-                Box::new(validate_response),
-            ),
-        }
+        Self::with_transport(
+            version,
+            Transport::Unix {
+                client: Client::builder()
+                    .pool_max_idle_per_host(0)
+                    .build(get_unix_connector()),
+                path: socket_path.as_ref().to_path_buf(),
+            },
+        )
+    }
+
+    #[cfg(all(unix, feature = "ssh"))]
+    #[cfg_attr(docsrs, doc(cfg(all(unix, feature = "ssh"))))]
+    /// Creates a new podman instance for a remote podman host reachable over SSH, for example a
+    /// build server or a `podman machine` VM.
+    ///
+    /// `uri` has the same format as the one accepted by `podman --url`, that is
+    /// `ssh://[user@]host[:port][/path/to/podman.sock][?secure=true]`. The `ssh://` prefix may be
+    /// omitted. If the socket path is missing it is queried from the remote host with `podman
+    /// info`.
+    ///
+    /// The connection is tunneled through the system `ssh` client which forwards the remote socket
+    /// to a private local unix socket for as long as this instance or any of its clones are alive.
+    /// `identity` is an optional path to a private key, when not provided the keys offered by an
+    /// SSH agent or the default identity files are used. Host keys are always verified against
+    /// `known_hosts`, with `secure=true` unknown hosts are rejected as well.
+    ///
+    /// This call blocks the current thread until the tunnel is established or fails, which may take
+    /// up to 30 seconds for an unreachable host. When running inside of an async runtime create the
+    /// client with [`tokio::task::spawn_blocking`](https://docs.rs/tokio/latest/tokio/task/fn.spawn_blocking.html)
+    /// or similar. To use a different timeout see
+    /// [`PodmanBuilder::connect_timeout`](PodmanBuilder::connect_timeout).
+    ///
+    ///  Uses [`LATEST_API_VERSION`](crate::LATEST_API_VERSION), to use a specific version see
+    ///  [`Podman::ssh_versioned`](Podman::ssh_versioned).
+    pub fn ssh<U, P>(uri: U, identity: Option<P>) -> Result<Podman>
+    where
+        U: AsRef<str>,
+        P: AsRef<Path>,
+    {
+        Self::ssh_versioned(uri, LATEST_API_VERSION, identity)
+    }
+
+    #[cfg(all(unix, feature = "ssh"))]
+    #[cfg_attr(docsrs, doc(cfg(all(unix, feature = "ssh"))))]
+    /// Same as [`Podman::ssh`](Podman::ssh) but the API version can be explicitly specified.
+    pub fn ssh_versioned<U, P>(
+        uri: U,
+        version: impl Into<ApiVersion>,
+        identity: Option<P>,
+    ) -> Result<Podman>
+    where
+        U: AsRef<str>,
+        P: AsRef<Path>,
+    {
+        Self::ssh_with_timeout(uri, version, identity, None)
+    }
+
+    #[cfg(all(unix, feature = "ssh"))]
+    fn ssh_with_timeout<U, P>(
+        uri: U,
+        version: impl Into<ApiVersion>,
+        identity: Option<P>,
+        connect_timeout: Option<Duration>,
+    ) -> Result<Podman>
+    where
+        U: AsRef<str>,
+        P: AsRef<Path>,
+    {
+        let uri = uri.as_ref();
+        let destination = if uri.starts_with("ssh://") {
+            SshDestination::parse(uri)
+        } else {
+            SshDestination::parse(&format!("ssh://{uri}"))
+        }?
+        .identity(identity.map(|p| p.as_ref().to_path_buf()))
+        .connect_timeout(connect_timeout);
+
+        let tunnel = SshTunnel::open(&destination)?;
+        let mut podman = Self::unix_versioned(tunnel.socket_path(), version);
+        podman._tunnel = Some(Arc::new(tunnel));
+        Ok(podman)
     }
 
     #[cfg(feature = "tls")]
@@ -156,18 +285,14 @@ impl Podman {
         H: AsRef<str>,
         P: AsRef<Path>,
     {
-        Ok(Podman {
-            version: version.into(),
-            client: RequestClient::new(
-                Transport::EncryptedTcp {
-                    client: Client::builder()
-                        .build(get_https_connector(cert_path.as_ref(), verify)?),
-                    host: url::url::Url::parse(&format!("https://{}", host.as_ref()))
-                        .map_err(Error::InvalidUrl)?,
-                },
-                Box::new(validate_response),
-            ),
-        })
+        Ok(Self::with_transport(
+            version,
+            Transport::EncryptedTcp {
+                client: Client::builder().build(get_https_connector(cert_path.as_ref(), verify)?),
+                host: url::url::Url::parse(&format!("https://{}", host.as_ref()))
+                    .map_err(Error::InvalidUrl)?,
+            },
+        ))
     }
 
     /// Creates a new podman instance for a podman host listening on a given TCP socket `host`.
@@ -192,17 +317,43 @@ impl Podman {
     where
         H: AsRef<str>,
     {
-        Ok(Podman {
-            version: version.into(),
-            client: RequestClient::new(
-                Transport::Tcp {
-                    client: Client::builder().build(get_http_connector()),
-                    host: url::url::Url::parse(&format!("tcp://{}", host.as_ref()))
-                        .map_err(Error::InvalidUrl)?,
-                },
-                Box::new(validate_response),
-            ),
-        })
+        Self::tcp_with_connector(host, version, get_http_connector())
+    }
+
+    fn tcp_with_connector<H>(
+        host: H,
+        version: impl Into<ApiVersion>,
+        connector: HttpConnector,
+    ) -> Result<Podman>
+    where
+        H: AsRef<str>,
+    {
+        Ok(Self::with_transport(
+            version,
+            Transport::Tcp {
+                client: Client::builder().build(connector),
+                host: url::url::Url::parse(&format!("tcp://{}", host.as_ref()))
+                    .map_err(Error::InvalidUrl)?,
+            },
+        ))
+    }
+
+    /// Creates a new Podman instance connected to the same service the podman CLI would use when
+    /// invoked without `--url` or `--connection`.
+    ///
+    /// The connection is resolved from `CONTAINER_HOST`/`CONTAINER_SSHKEY`,
+    /// `CONTAINER_CONNECTION`, the default connection from `podman-connections.json` or
+    /// `containers.conf` and finally the rootless and rootful podman sockets. See
+    /// [`config`](crate::config) for details.
+    pub fn from_env() -> Result<Podman> {
+        crate::config::default_connection()?.connect()
+    }
+
+    /// Creates a new Podman instance for a named connection, the same way
+    /// `podman --connection <name>` does. Named connections are read from
+    /// `podman-connections.json` and `containers.conf`.
+    pub fn from_connection(name: impl AsRef<str>) -> Result<Podman> {
+        crate::config::find_connection(name)?.connect()
     }
 
     /// Verifies the API version returned by the server and adjusts the version used by this client
@@ -222,6 +373,97 @@ impl Podman {
         Ok(())
     }
 
+    /// Returns the capabilities of the connected podman service. The server is queried on first use
+    /// and the result is shared by all clones of this instance.
+    ///
+    /// Example:
+    ///
+    /// ```no_run
+    /// async {
+    ///     use podman_api::Podman;
+    ///     let podman = Podman::unix("/run/user/1000/podman/podman.sock");
+    ///
+    ///     match podman.capabilities().await {
+    ///         Ok(caps) if caps.supports((5, 0)) => println!("podman 5 or newer"),
+    ///         Ok(caps) => println!("podman {:?}", caps.podman_version),
+    ///         Err(e) => eprintln!("{}", e),
+    ///     }
+    /// };
+    /// ```
+    pub async fn capabilities(&self) -> Result<models::Capabilities> {
+        let mut capabilities = match self.capabilities.get() {
+            Some(capabilities) => capabilities.clone(),
+            None => {
+                let ep = self.version.make_endpoint("/libpod/_ping");
+                let ping = self
+                    .send(&ep, Retry::Idempotent, || self.client.get(&ep))
+                    .await
+                    .and_then(|resp| models::LibpodPingInfo::try_from(resp.headers()))?;
+                let ep = self.version.make_endpoint("/libpod/version");
+                let version = self
+                    .send(&ep, Retry::Idempotent, || self.client.get_json(&ep))
+                    .await?;
+                let capabilities = models::Capabilities::new(ping, version)?;
+                self.capabilities.get_or_init(|| capabilities).clone()
+            }
+        };
+        capabilities.api_version = capabilities.server_api_version.min(self.version);
+        Ok(capabilities)
+    }
+
+    /// Fails with [`Error::UnsupportedByServer`](Error::UnsupportedByServer) if the server is
+    /// older than `required`.
+    pub(crate) async fn require_api_version(&self, required: impl Into<ApiVersion>) -> Result<()> {
+        self.capabilities().await?.require(required)
+    }
+
+    /// Returns the `X-Registry-Auth` header used to pull or push the image `reference`. The
+    /// `explicit` credentials of the opts take precedence over the configured
+    /// [`CredentialStore`](crate::credentials::CredentialStore).
+    pub(crate) async fn registry_auth(
+        &self,
+        explicit: Option<String>,
+        reference: Option<&str>,
+    ) -> Result<Option<Headers>> {
+        let auth = match (explicit, reference, &self.config.credentials) {
+            (Some(auth), _, _) => Some(auth),
+            (None, Some(reference), Some(store)) => {
+                let store = store.clone();
+                let reference = reference.to_owned();
+                lookup_credentials(move || store.get_for_image(reference))
+                    .await?
+                    .map(|auth| auth.serialize())
+            }
+            _ => None,
+        };
+        Ok(auth.map(|auth| Headers::single(conn::AUTH_HEADER, auth)))
+    }
+
+    /// Returns the `X-Registry-Config` header with the credentials of all registries known to
+    /// the configured [`CredentialStore`](crate::credentials::CredentialStore), used by builds.
+    pub(crate) async fn registry_config(&self) -> Result<Option<Headers>> {
+        let Some(store) = self.config.credentials.clone() else {
+            return Ok(None);
+        };
+        Ok(lookup_credentials(move || store.registry_config())
+            .await?
+            .map(|config| Headers::single(REGISTRY_CONFIG_HEADER, config)))
+    }
+
+    /// API version used in request paths. When version negotiation is enabled this is lowered to
+    /// the version supported by the server.
+    async fn api_version(&self) -> Result<ApiVersion> {
+        if self.config.negotiate_version {
+            Ok(self.capabilities().await?.api_version)
+        } else {
+            Ok(self.version)
+        }
+    }
+
+    async fn make_endpoint(&self, endpoint: impl AsRef<str>) -> Result<String> {
+        Ok(self.api_version().await?.make_endpoint(endpoint))
+    }
+
     //####################################################################################################
     //
     // API handles
@@ -528,20 +770,14 @@ impl Podman {
         resource: ApiResource,
         id: &crate::Id,
     ) -> Result<bool> {
-        use crate::conn::http::StatusCode;
         let ep = format!("/libpod/{}/{}/exists", resource.as_ref(), id);
         match self.get(&ep).await {
             Ok(resp) => match resp.status() {
                 StatusCode::NO_CONTENT => Ok(true),
                 _ => Ok(false),
             },
-            Err(e) => match e {
-                Error::Fault {
-                    code: StatusCode::NOT_FOUND,
-                    message: _,
-                } => Ok(false),
-                e => Err(e),
-            },
+            Err(e) if e.is_not_found() => Ok(false),
+            Err(e) => Err(e),
         }
     }
 
@@ -568,13 +804,57 @@ impl Podman {
     // Request helpers
     //####################################################################################################
 
+    /// Runs the request created by `request` applying the configured timeout and retrying
+    /// it according to the retry policy if the error allows it.
+    async fn send<F, Fut, T>(&self, endpoint: &str, retry: Retry, request: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        self.send_with_timeout(endpoint, retry, self.config.request_timeout, request)
+            .await
+    }
+
+    async fn send_with_timeout<F, Fut, T>(
+        &self,
+        endpoint: &str,
+        retry: Retry,
+        timeout: Option<Duration>,
+        mut request: F,
+    ) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 0;
+        loop {
+            let result = match timeout {
+                Some(timeout) => tokio::time::timeout(timeout, request())
+                    .await
+                    .unwrap_or(Err(Error::Timeout(timeout))),
+                None => request().await,
+            };
+            match result {
+                Err(e) if attempt < self.config.retry.max_retries && retry.allows(&e) => {
+                    let backoff = self.config.retry.backoff(attempt);
+                    log::debug!("request to {endpoint} failed, retrying in {backoff:?} - {e}");
+                    tokio::time::sleep(backoff).await;
+                    attempt += 1;
+                }
+                result => return result.map_err(|e| e.with_endpoint(endpoint)),
+            }
+        }
+    }
+
     pub(crate) async fn get(&self, endpoint: impl AsRef<str>) -> Result<Response<Body>> {
-        self.client.get(self.version.make_endpoint(endpoint)).await
+        let ep = self.make_endpoint(endpoint).await?;
+        self.send(&ep, Retry::Idempotent, || self.client.get(&ep))
+            .await
     }
 
     pub(crate) async fn get_string(&self, endpoint: impl AsRef<str>) -> Result<String> {
-        self.client
-            .get_string(self.version.make_endpoint(endpoint))
+        let ep = self.make_endpoint(endpoint).await?;
+        self.send(&ep, Retry::Idempotent, || self.client.get_string(&ep))
             .await
     }
 
@@ -582,16 +862,54 @@ impl Podman {
         &self,
         endpoint: impl AsRef<str>,
     ) -> Result<T> {
-        self.client
-            .get_json(self.version.make_endpoint(endpoint))
+        let ep = self.make_endpoint(endpoint).await?;
+        self.send(&ep, Retry::Idempotent, || self.client.get_json(&ep))
             .await
     }
 
+    /// Same as [`get_json`](Podman::get_json) but sends `headers` along with the request.
+    pub(crate) async fn get_json_with_headers<T: DeserializeOwned>(
+        &self,
+        endpoint: impl AsRef<str>,
+        headers: Option<Headers>,
+    ) -> Result<T> {
+        let Some(headers) = headers else {
+            return self.get_json(endpoint).await;
+        };
+        let ep = self.make_endpoint(endpoint).await?;
+        let body = self
+            .send(&ep, Retry::Idempotent, || async {
+                let mut request = Request::get(self.transport.make_uri(&ep)?);
+                for (key, value) in headers.clone() {
+                    request = request.header(key, value);
+                }
+                let request = request
+                    .body(Body::empty())
+                    .map_err(|e| Error::StringError(e.to_string()))?;
+                let response = validate_response(self.transport.request(request).await?).await?;
+                hyper::body::to_bytes(response.into_body())
+                    .await
+                    .map_err(|e| Error::from(conn::Error::from(e)))
+            })
+            .await?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+
     pub(crate) fn get_stream(
         &'_ self,
         endpoint: impl AsRef<str>,
     ) -> impl Stream<Item = Result<Bytes>> + '_ {
-        self.client.get_stream(self.version.make_endpoint(endpoint))
+        let endpoint = endpoint.as_ref().to_string();
+        let stream = async move {
+            let ep = self.make_endpoint(endpoint).await?;
+            Ok::<_, Error>(
+                self.client
+                    .get_stream(ep.clone())
+                    .map_err(move |e| e.with_endpoint(&ep)),
+            )
+        }
+        .try_flatten_stream();
+        with_idle_timeout(stream, self.config.stream_idle_timeout)
     }
 
     pub(crate) fn get_json_stream<'client, T>(
@@ -601,8 +919,16 @@ impl Podman {
     where
         T: DeserializeOwned + 'client,
     {
-        self.client
-            .get_json_stream(self.version.make_endpoint(endpoint))
+        let stream = async move {
+            let ep = self.make_endpoint(endpoint).await?;
+            Ok::<_, Error>(
+                self.client
+                    .get_json_stream(ep.clone())
+                    .map_err(move |e| e.with_endpoint(&ep)),
+            )
+        }
+        .try_flatten_stream();
+        with_idle_timeout(stream, self.config.stream_idle_timeout)
     }
 
     pub(crate) async fn post<B>(
@@ -614,9 +940,14 @@ impl Podman {
     where
         B: Into<Body>,
     {
-        self.client
-            .post(self.version.make_endpoint(endpoint), body, headers)
-            .await
+        let ep = self.make_endpoint(endpoint).await?;
+        let retry = Retry::for_payload(&body);
+        let mut body = Some(body);
+        self.send(&ep, retry, || {
+            let body = body.take().unwrap_or(Payload::None);
+            self.client.post(&ep, body, headers.clone())
+        })
+        .await
     }
 
     pub(crate) async fn post_string<B>(
@@ -628,9 +959,30 @@ impl Podman {
     where
         B: Into<Body>,
     {
-        self.client
-            .post_string(self.version.make_endpoint(endpoint), body, headers)
-            .await
+        let ep = self.make_endpoint(endpoint).await?;
+        let retry = Retry::for_payload(&body);
+        let mut body = Some(body);
+        self.send(&ep, retry, || {
+            let body = body.take().unwrap_or(Payload::None);
+            self.client.post_string(&ep, body, headers.clone())
+        })
+        .await
+    }
+
+    /// Makes a POST request without a body to an endpoint that blocks until some condition is met.
+    /// The configured request timeout doesn't apply, instead the request fails once `timeout`
+    /// elapses.
+    pub(crate) async fn post_blocking(
+        &self,
+        endpoint: impl AsRef<str>,
+        timeout: Option<Duration>,
+    ) -> Result<String> {
+        let ep = self.make_endpoint(endpoint).await?;
+        self.send_with_timeout(&ep, Retry::ConnectionRefused, timeout, || {
+            self.client
+                .post_string(&ep, Payload::empty(), Headers::none())
+        })
+        .await
     }
 
     pub(crate) async fn post_json<B, T>(
@@ -643,9 +995,14 @@ impl Podman {
         T: DeserializeOwned,
         B: Into<Body>,
     {
-        self.client
-            .post_json(self.version.make_endpoint(endpoint), body, headers)
-            .await
+        let ep = self.make_endpoint(endpoint).await?;
+        let retry = Retry::for_payload(&body);
+        let mut body = Some(body);
+        self.send(&ep, retry, || {
+            let body = body.take().unwrap_or(Payload::None);
+            self.client.post_json(&ep, body, headers.clone())
+        })
+        .await
     }
 
     pub(crate) fn post_stream<'client, B>(
@@ -657,8 +1014,17 @@ impl Podman {
     where
         B: Into<Body> + 'client,
     {
-        self.client
-            .post_stream(self.version.make_endpoint(endpoint), body, headers)
+        let endpoint = endpoint.as_ref().to_string();
+        let stream = async move {
+            let ep = self.make_endpoint(endpoint).await?;
+            Ok::<_, Error>(
+                self.client
+                    .post_stream(ep.clone(), body, headers)
+                    .map_err(move |e| e.with_endpoint(&ep)),
+            )
+        }
+        .try_flatten_stream();
+        with_idle_timeout(stream, self.config.stream_idle_timeout)
     }
 
     pub(crate) async fn post_upgrade_stream<B>(
@@ -669,8 +1035,11 @@ impl Podman {
     where
         B: Into<Body>,
     {
-        let ep = self.version.make_endpoint(endpoint);
-        self.client.post_upgrade_stream(ep, body).await
+        let ep = self.make_endpoint(endpoint).await?;
+        self.client
+            .post_upgrade_stream(ep.clone(), body)
+            .await
+            .map_err(|e| e.with_endpoint(&ep))
     }
 
     pub(crate) async fn put<B>(
@@ -681,14 +1050,25 @@ impl Podman {
     where
         B: Into<Body>,
     {
-        self.client
-            .put(self.version.make_endpoint(endpoint), body)
+        let ep = self.make_endpoint(endpoint).await?;
+        let retry = Retry::for_payload(&body);
+        let mut body = Some(body);
+        self.send(&ep, retry, || {
+            let body = body.take().unwrap_or(Payload::None);
+            self.client.put(&ep, body)
+        })
+        .await
+    }
+
+    pub(crate) async fn head(&self, endpoint: impl AsRef<str>) -> Result<Response<Body>> {
+        let ep = self.make_endpoint(endpoint).await?;
+        self.send(&ep, Retry::Idempotent, || self.client.head(&ep))
             .await
     }
 
     pub(crate) async fn delete(&self, endpoint: impl AsRef<str>) -> Result<Response<Body>> {
-        self.client
-            .delete(self.version.make_endpoint(endpoint))
+        let ep = self.make_endpoint(endpoint).await?;
+        self.send(&ep, Retry::ConnectionRefused, || self.client.delete(&ep))
             .await
     }
 
@@ -696,9 +1076,264 @@ impl Podman {
         &self,
         endpoint: impl AsRef<str>,
     ) -> Result<T> {
-        self.client
-            .delete_json(self.version.make_endpoint(endpoint))
-            .await
+        let ep = self.make_endpoint(endpoint).await?;
+        self.send(&ep, Retry::ConnectionRefused, || {
+            self.client.delete_json(&ep)
+        })
+        .await
+    }
+}
+
+/// Wraps a response stream so that it fails with [`Error::Timeout`](Error::Timeout) once no item
+/// arrives for longer than `timeout`.
+fn with_idle_timeout<'a, T: 'a>(
+    stream: impl Stream<Item = Result<T>> + 'a,
+    timeout: Option<Duration>,
+) -> impl Stream<Item = Result<T>> + 'a {
+    futures_util::stream::unfold(
+        (Box::pin(stream), false),
+        move |(mut stream, timed_out)| async move {
+            if timed_out {
+                return None;
+            }
+            let next = match timeout {
+                Some(timeout) => match tokio::time::timeout(timeout, stream.next()).await {
+                    Ok(next) => next,
+                    Err(_) => return Some((Err(Error::Timeout(timeout)), (stream, true))),
+                },
+                None => stream.next().await,
+            };
+            next.map(|item| (item, (stream, false)))
+        },
+    )
+}
+
+/// Runs a lookup of a [`CredentialStore`] on the blocking thread pool as it reads auth files and
+/// spawns credential helpers.
+async fn lookup_credentials<T, F>(lookup: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(lookup)
+        .await
+        .unwrap_or_else(|e| Err(Error::IO(std::io::Error::other(e))))
+}
+
+#[derive(Debug, Clone, Copy)]
+/// Which failed requests may be sent again.
+enum Retry {
+    /// Safe to repeat, retried on connection errors, timeouts and temporarily unavailable
+    /// service.
+    Idempotent,
+    /// Only retried when the connection couldn't be established so the request never reached
+    /// podman.
+    ConnectionRefused,
+    /// The request body can't be sent twice.
+    Never,
+}
+
+impl Retry {
+    fn for_payload<B: Into<Body>>(payload: &Payload<B>) -> Self {
+        if payload.is_none() {
+            Retry::ConnectionRefused
+        } else {
+            Retry::Never
+        }
+    }
+
+    fn allows(&self, error: &Error) -> bool {
+        match self {
+            Retry::Idempotent => {
+                error.is_connect_error()
+                    || error.is_timeout()
+                    || matches!(
+                        error.status_code(),
+                        Some(
+                            StatusCode::BAD_GATEWAY
+                                | StatusCode::SERVICE_UNAVAILABLE
+                                | StatusCode::GATEWAY_TIMEOUT
+                        )
+                    )
+            }
+            Retry::ConnectionRefused => error.is_connect_error(),
+            Retry::Never => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct RequestConfig {
+    negotiate_version: bool,
+    request_timeout: Option<Duration>,
+    stream_idle_timeout: Option<Duration>,
+    retry: RetryPolicy,
+    credentials: Option<CredentialStore>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Policy deciding how many times and how often failed requests are retried.
+///
+/// Only requests that are safe to repeat are retried, that is `GET` requests failing with a
+/// connection error, a timeout or a `502`, `503` or `504` response, and requests without a body
+/// which failed to connect to podman at all. The delay between attempts grows exponentially from
+/// `initial_backoff` up to `max_backoff`.
+pub struct RetryPolicy {
+    max_retries: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    multiplier: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl RetryPolicy {
+    /// Never retry failed requests. This is the default.
+    pub fn none() -> Self {
+        Self::exponential(0)
+    }
+
+    /// Retry failed requests up to `max_retries` times starting with a 100ms delay that doubles
+    /// with each attempt up to 10s.
+    pub fn exponential(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            multiplier: 2.,
+        }
+    }
+
+    /// Delay before the first retry.
+    pub fn initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    /// Upper bound of the delay between retries.
+    pub fn max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    /// Factor by which the delay grows after each retry.
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier.max(1.);
+        self
+    }
+
+    /// Delay before the retry number `attempt` counted from 0.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = self.multiplier.powi(attempt.min(i32::MAX as u32) as i32);
+        self.initial_backoff
+            .mul_f64(factor.min(u32::MAX as f64))
+            .min(self.max_backoff)
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Builder of a [`Podman`](Podman) instance with configurable timeouts and retries. Created with
+/// [`Podman::builder`](Podman::builder).
+pub struct PodmanBuilder {
+    uri: String,
+    version: ApiVersion,
+    connect_timeout: Option<Duration>,
+    config: RequestConfig,
+}
+
+impl PodmanBuilder {
+    fn new(uri: impl Into<String>) -> Self {
+        Self {
+            uri: uri.into(),
+            version: LATEST_API_VERSION,
+            connect_timeout: None,
+            config: RequestConfig::default(),
+        }
+    }
+
+    /// API version used by the client, defaults to [`LATEST_API_VERSION`](crate::LATEST_API_VERSION).
+    pub fn version(mut self, version: impl Into<ApiVersion>) -> Self {
+        self.version = version.into();
+        self
+    }
+
+    /// Negotiate the API version with the server before the first request. The version used in
+    /// request paths is lowered to the one supported by the server so that the same client can
+    /// talk to older and newer podman services. Disabled by default.
+    pub fn negotiate_version(mut self, negotiate: bool) -> Self {
+        self.config.negotiate_version = negotiate;
+        self
+    }
+
+    /// Maximum time a single request may take until a response is received. Streaming
+    /// endpoints are not affected, see
+    /// [`stream_idle_timeout`](PodmanBuilder::stream_idle_timeout).
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.config.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Maximum time spent establishing a connection. Applies to `tcp://` and `http://` URIs,
+    /// and to `ssh://` URIs where it bounds both the `ssh` connection and the wait for the
+    /// forwarded socket (30 seconds when not set). Connections over a unix socket are only
+    /// bounded by the [`request_timeout`](PodmanBuilder::request_timeout). TLS connections
+    /// created with [`Podman::tls`](Podman::tls) aren't created through this builder and use no
+    /// connect timeout.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the policy of retrying failed requests, by default requests are never retried.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.config.retry = policy;
+        self
+    }
+
+    /// Maximum time streaming endpoints like [`Podman::events`](Podman::events),
+    /// [`Container::logs`](crate::api::Container::logs) or
+    /// [`Containers::stats_stream`](crate::api::Containers::stats_stream) may wait for the next
+    /// item before failing with [`Error::Timeout`](crate::Error::Timeout). By default streams
+    /// wait indefinitely.
+    pub fn stream_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.config.stream_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Attaches credentials resolved from `store` to pulls, pushes, searches and builds that
+    /// don't set credentials explicitly. Use
+    /// [`CredentialStore::from_env`](crate::credentials::CredentialStore::from_env) to use the
+    /// same credentials as the podman CLI.
+    pub fn registry_credentials(mut self, store: CredentialStore) -> Self {
+        self.config.credentials = Some(store);
+        self
+    }
+
+    /// Creates the Podman instance. Returns an error if the URI is invalid or uses an
+    /// unsupported scheme. With an `ssh://` URI this call blocks the current thread, see
+    /// [`Podman::ssh`](Podman::ssh).
+    pub fn build(self) -> Result<Podman> {
+        let mut podman = match self.uri.split_once("://") {
+            Some(("tcp" | "http", host)) => {
+                let mut connector = get_http_connector();
+                connector.set_connect_timeout(self.connect_timeout);
+                Podman::tcp_with_connector(host, self.version, connector)?
+            }
+            #[cfg(all(unix, feature = "ssh"))]
+            Some(("ssh", _)) => Podman::ssh_with_timeout(
+                &self.uri,
+                self.version,
+                None::<&Path>,
+                self.connect_timeout,
+            )?,
+            _ => Podman::new_versioned(&self.uri, self.version)?,
+        };
+        podman.config = self.config;
+        Ok(podman)
     }
 }
 
@@ -728,29 +1363,23 @@ fn validate_response(
                     .map_err(conn::Error::from)?;
                 let message_body = String::from_utf8(bytes.to_vec()).map_err(conn::Error::from)?;
                 log::trace!("{message_body:#?}");
-                match serde_json::from_str::<models::ErrorModel>(&message_body) {
-                    Ok(error) => {
-                        let mut message = format!(
-                            "{}: {}",
-                            error.message.unwrap_or_default(),
-                            error.cause.unwrap_or_default(),
-                        );
-                        if message == ": " {
-                            message = status
-                                .canonical_reason()
-                                .unwrap_or("unknown error code")
-                                .to_owned();
-                        }
-                        Err(Error::Fault {
-                            code: status,
-                            message,
-                        })
-                    }
-                    Err(_) => Err(Error::Fault {
+                let error = match serde_json::from_str::<models::ErrorModel>(&message_body) {
+                    Ok(error) => ApiError {
                         code: status,
-                        message: message_body,
-                    }),
-                }
+                        endpoint: String::new(),
+                        cause: error.cause,
+                        message: error.message,
+                        response: error.response,
+                    },
+                    Err(_) => ApiError {
+                        code: status,
+                        endpoint: String::new(),
+                        cause: None,
+                        message: Some(message_body),
+                        response: None,
+                    },
+                };
+                Err(Error::from_api_error(error))
             }
         }
     })
@@ -798,4 +1427,180 @@ mod tests {
             e => panic!(r#"Expected Error::UnsupportedScheme(""), got {e}"#),
         }
     }
+
+    #[test]
+    fn classifies_error_responses() {
+        use crate::api::ApiResource;
+        use crate::conn::hyper::StatusCode;
+        use crate::ApiError;
+
+        let error = |code, cause: &str| ApiError {
+            code,
+            endpoint: String::new(),
+            cause: Some(cause.into()),
+            message: Some("error".into()),
+            response: Some(code.as_u16().into()),
+        };
+
+        let err = Error::from_api_error(error(StatusCode::NOT_FOUND, "no such container"))
+            .with_endpoint("/v4.3.1/libpod/containers/test-container/json?size=true");
+        assert!(err.is_not_found());
+        match &err {
+            Error::NotFound {
+                resource,
+                id,
+                error,
+            } => {
+                assert_eq!(*resource, Some(ApiResource::Containers));
+                assert_eq!(id.as_deref(), Some("test-container"));
+                assert_eq!(
+                    error.endpoint,
+                    "/v4.3.1/libpod/containers/test-container/json?size=true"
+                );
+            }
+            e => panic!("expected Error::NotFound, got {e}"),
+        }
+
+        let err = Error::from_api_error(error(StatusCode::NOT_FOUND, "image not known"))
+            .with_endpoint("/v4.3.1/libpod/images/docker.io/library/alpine/json");
+        match err {
+            Error::NotFound { resource, id, .. } => {
+                assert_eq!(resource, Some(ApiResource::Images));
+                assert_eq!(id.as_deref(), Some("docker.io/library/alpine"));
+            }
+            e => panic!("expected Error::NotFound, got {e}"),
+        }
+
+        // a name with slashes and no action
+        let err = Error::from_api_error(error(StatusCode::NOT_FOUND, "image not known"))
+            .with_endpoint("/v4.3.1/libpod/images/docker.io/library/alpine:latest?force=true");
+        match err {
+            Error::NotFound { resource, id, .. } => {
+                assert_eq!(resource, Some(ApiResource::Images));
+                assert_eq!(id.as_deref(), Some("docker.io/library/alpine:latest"));
+            }
+            e => panic!("expected Error::NotFound, got {e}"),
+        }
+
+        // collection endpoints don't name a resource
+        for (endpoint, expected) in [
+            ("/v4.3.1/libpod/containers/create", ApiResource::Containers),
+            ("/v4.3.1/libpod/containers/json?all=true", ApiResource::Containers),
+            ("/v4.3.1/libpod/images/pull?reference=alpine", ApiResource::Images),
+            ("/v4.3.1/libpod/networks/prune", ApiResource::Networks),
+        ] {
+            let err = Error::from_api_error(error(StatusCode::NOT_FOUND, "image not known"))
+                .with_endpoint(endpoint);
+            match err {
+                Error::NotFound { resource, id, .. } => {
+                    assert_eq!(resource, Some(expected));
+                    assert_eq!(id, None, "{endpoint}");
+                }
+                e => panic!("expected Error::NotFound, got {e}"),
+            }
+        }
+
+        let err = Error::from_api_error(error(StatusCode::CONFLICT, "volume is being used"));
+        assert!(err.is_in_use() && err.is_conflict());
+        let err = Error::from_api_error(error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "container already exists",
+        ));
+        assert!(err.is_already_exists());
+        let err = Error::from_api_error(error(StatusCode::CONFLICT, "container is paused"));
+        assert!(matches!(err, Error::Conflict(_)));
+        let err = Error::from_api_error(error(StatusCode::NOT_MODIFIED, ""));
+        assert!(err.is_not_modified());
+        let err = Error::from_api_error(error(StatusCode::UNAUTHORIZED, ""));
+        assert!(err.is_unauthorized());
+        let err = Error::from_api_error(error(StatusCode::BAD_REQUEST, "invalid filter"));
+        assert!(matches!(err, Error::Fault(_)));
+        assert_eq!(err.status_code(), Some(StatusCode::BAD_REQUEST));
+        assert_eq!(
+            err.to_string(),
+            "error 400 Bad Request - error: invalid filter"
+        );
+    }
+
+    #[test]
+    fn computes_retry_backoff() {
+        use super::RetryPolicy;
+        use std::time::Duration;
+
+        let policy = RetryPolicy::exponential(10)
+            .initial_backoff(Duration::from_millis(100))
+            .max_backoff(Duration::from_secs(1));
+        assert_eq!(policy.backoff(0), Duration::from_millis(100));
+        assert_eq!(policy.backoff(1), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(800));
+        assert_eq!(policy.backoff(4), Duration::from_secs(1));
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn retries_refused_connections() {
+        use super::RetryPolicy;
+        use std::time::{Duration, Instant};
+
+        let podman = Podman::builder("tcp://127.0.0.1:1")
+            .connect_timeout(Duration::from_secs(1))
+            .retry_policy(RetryPolicy::exponential(2).initial_backoff(Duration::from_millis(50)))
+            .build()
+            .unwrap();
+
+        let start = Instant::now();
+        let err = podman.ping().await.unwrap_err();
+        assert!(err.is_connect_error(), "{err}");
+        assert!(start.elapsed() >= Duration::from_millis(150));
+    }
+
+    #[tokio::test]
+    async fn times_out_idle_streams() {
+        use futures_util::StreamExt;
+        use std::time::Duration;
+
+        let stream = super::with_idle_timeout(
+            futures_util::stream::once(async { Ok(1) })
+                .chain(futures_util::stream::pending::<crate::Result<i32>>()),
+            Some(Duration::from_millis(10)),
+        );
+        let items = stream.collect::<Vec<_>>().await;
+        assert_eq!(items.len(), 2);
+        assert!(matches!(items[0], Ok(1)));
+        assert!(items[1].as_ref().unwrap_err().is_timeout());
+    }
+
+    #[test]
+    fn checks_required_api_version() {
+        use crate::opts::ContainerCreateOpts;
+        use crate::ApiVersion;
+
+        let opts = ContainerCreateOpts::builder().image("alpine").build();
+        assert_eq!(opts.required_api_version(), None);
+        let opts = ContainerCreateOpts::builder()
+            .image("alpine")
+            .health_check_on_failure_action(1)
+            .build();
+        let required = opts.required_api_version().unwrap();
+        assert_eq!(required, ApiVersion::new(4, Some(3), None));
+
+        let capabilities = crate::models::Capabilities {
+            api_version: ApiVersion::new(4, Some(2), Some(0)),
+            server_api_version: ApiVersion::new(4, Some(2), Some(0)),
+            min_api_version: Some(ApiVersion::new(4, Some(0), Some(0))),
+            compat_api_version: "1.41".into(),
+            podman_version: Some("4.2.0".into()),
+            buildah_version: "1.27.0".into(),
+            os: None,
+            arch: None,
+        };
+        assert!(capabilities.supports((4, 2)));
+        match capabilities.require(required) {
+            Err(Error::UnsupportedByServer { required, actual }) => {
+                assert_eq!(required, ApiVersion::new(4, Some(3), None));
+                assert_eq!(actual, ApiVersion::new(4, Some(2), Some(0)));
+            }
+            res => panic!("expected Error::UnsupportedByServer, got {res:?}"),
+        }
+    }
 }
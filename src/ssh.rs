@@ -0,0 +1,348 @@
+//! SSH tunnel used to reach a remote podman socket.
+//!
+//! The tunnel is established by spawning the system `ssh` client which forwards the remote unix
+//! socket to a private local socket. Authentication and host key verification are handled by
+//! OpenSSH, so identity files, a running SSH agent, `~/.ssh/config` and `known_hosts` all work the
+//! same way they do for the podman CLI.
+
+use crate::{Error, Result};
+
+use std::fs::{self, DirBuilder, File};
+use std::os::unix::fs::DirBuilderExt;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+static TUNNEL_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Parsed form of an `ssh://[user@]host[:port][/path/to/podman.sock][?secure=true]` URI.
+pub(crate) struct SshDestination {
+    pub user: Option<String>,
+    pub host: String,
+    pub port: Option<u16>,
+    pub socket_path: Option<PathBuf>,
+    pub identity: Option<PathBuf>,
+    pub secure: bool,
+    pub connect_timeout: Duration,
+}
+
+impl SshDestination {
+    /// Parses a full `ssh://` URI as accepted by `podman --url`.
+    pub fn parse(uri: &str) -> Result<Self> {
+        let url = url::Url::parse(uri).map_err(Error::InvalidUrl)?;
+        if url.scheme() != "ssh" {
+            return Err(Error::UnsupportedScheme(url.scheme().to_string()));
+        }
+
+        let host = url
+            .host_str()
+            .filter(|host| !host.is_empty())
+            .ok_or(Error::MissingAuthority)?
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_string();
+
+        let user = Some(url.username())
+            .filter(|user| !user.is_empty())
+            .map(ToString::to_string);
+
+        let socket_path = Some(url.path())
+            .filter(|path| !path.is_empty() && *path != "/")
+            .map(PathBuf::from);
+
+        let secure = url
+            .query_pairs()
+            .find(|(key, _)| key == "secure")
+            .map(|(_, value)| {
+                value
+                    .parse::<bool>()
+                    .or_else(|_| value.to_lowercase().parse())
+            })
+            .transpose()
+            .map_err(|e| Error::StringError(format!("invalid `secure` parameter - {e}")))?
+            .unwrap_or_default();
+
+        Ok(Self {
+            user,
+            host,
+            port: url.port(),
+            socket_path,
+            identity: None,
+            secure,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+        })
+    }
+
+    /// Sets the identity file used to authenticate. When not set, keys from the SSH agent and the
+    /// default identity files are used.
+    pub fn identity(mut self, identity: Option<PathBuf>) -> Self {
+        self.identity = identity;
+        self
+    }
+
+    /// Sets the maximum time spent connecting to the host and waiting for the tunnel, 30
+    /// seconds when not set.
+    pub fn connect_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.connect_timeout = timeout.unwrap_or(DEFAULT_CONNECT_TIMEOUT);
+        self
+    }
+
+    fn target(&self) -> String {
+        match &self.user {
+            Some(user) => format!("{user}@{}", self.host),
+            None => self.host.clone(),
+        }
+    }
+
+    /// Common arguments passed to every `ssh` invocation for this destination.
+    fn base_args(&self) -> Vec<String> {
+        let mut args = vec![
+            "-o".into(),
+            "BatchMode=yes".into(),
+            "-o".into(),
+            // ssh only accepts whole seconds
+            format!(
+                "ConnectTimeout={}",
+                self.connect_timeout.as_secs_f64().ceil().max(1.0)
+            ),
+            "-o".into(),
+            // A host with a changed key is always rejected. When `secure` is set the host must
+            // also already be present in known_hosts.
+            if self.secure {
+                "StrictHostKeyChecking=yes".into()
+            } else {
+                "StrictHostKeyChecking=accept-new".into()
+            },
+        ];
+        if let Some(port) = self.port {
+            args.push("-p".into());
+            args.push(port.to_string());
+        }
+        if let Some(identity) = &self.identity {
+            args.push("-i".into());
+            args.push(identity.to_string_lossy().into_owned());
+            args.push("-o".into());
+            args.push("IdentitiesOnly=yes".into());
+        }
+        args
+    }
+
+    /// Arguments of the `ssh` process forwarding `local` socket to the remote `socket_path`.
+    pub(crate) fn forward_args(&self, local: &Path, remote: &Path) -> Vec<String> {
+        let mut args = self.base_args();
+        args.extend([
+            "-N".into(),
+            "-T".into(),
+            "-o".into(),
+            "ExitOnForwardFailure=yes".into(),
+            "-o".into(),
+            "StreamLocalBindUnlink=yes".into(),
+            "-L".into(),
+            format!("{}:{}", local.display(), remote.display()),
+            "--".into(),
+            self.target(),
+        ]);
+        args
+    }
+
+    /// Asks the remote podman for the path of its API socket. Used when the URI doesn't contain
+    /// one.
+    fn remote_socket_path(&self) -> Result<PathBuf> {
+        let mut args = self.base_args();
+        args.extend([
+            "--".into(),
+            self.target(),
+            "podman".into(),
+            "info".into(),
+            "--format".into(),
+            "'{{.Host.RemoteSocket.Path}}'".into(),
+        ]);
+        let output = Command::new("ssh")
+            .args(args)
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|e| Error::SshConnection(format!("failed to run ssh - {e}")))?;
+
+        if !output.status.success() {
+            return Err(Error::SshConnection(format!(
+                "failed to query remote socket path - {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        let path = String::from_utf8_lossy(&output.stdout)
+            .trim()
+            .trim_start_matches("unix://")
+            .to_string();
+        if path.is_empty() {
+            return Err(Error::SshConnection(
+                "remote podman didn't report an API socket path".into(),
+            ));
+        }
+        Ok(PathBuf::from(path))
+    }
+}
+
+#[derive(Debug)]
+/// A running `ssh` process forwarding a remote podman socket to a local one. The process is
+/// terminated and the local socket removed once this tunnel is dropped.
+pub(crate) struct SshTunnel {
+    child: Mutex<Child>,
+    dir: PathBuf,
+    local_socket: PathBuf,
+}
+
+impl SshTunnel {
+    /// Spawns `ssh` and waits until the forwarded local socket becomes available.
+    pub fn open(destination: &SshDestination) -> Result<Self> {
+        let remote = match &destination.socket_path {
+            Some(path) => path.clone(),
+            None => destination.remote_socket_path()?,
+        };
+
+        let dir = std::env::temp_dir().join(format!(
+            "podman-api-ssh-{}-{}",
+            std::process::id(),
+            TUNNEL_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        DirBuilder::new().mode(0o700).create(&dir)?;
+        let local_socket = dir.join("podman.sock");
+        let log_path = dir.join("ssh.log");
+
+        let spawned = File::create(&log_path).and_then(|log| {
+            Command::new("ssh")
+                .args(destination.forward_args(&local_socket, &remote))
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(log)
+                .spawn()
+        });
+        let child = match spawned {
+            Ok(child) => child,
+            Err(e) => {
+                let _ = fs::remove_dir_all(&dir);
+                return Err(Error::SshConnection(format!("failed to run ssh - {e}")));
+            }
+        };
+
+        let tunnel = SshTunnel {
+            child: Mutex::new(child),
+            dir,
+            local_socket,
+        };
+        tunnel.wait_ready(&log_path, destination.connect_timeout)?;
+        Ok(tunnel)
+    }
+
+    fn wait_ready(&self, log_path: &Path, timeout: Duration) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if self.local_socket.exists() {
+                return Ok(());
+            }
+
+            let status = self
+                .child
+                .lock()
+                .map_err(|_| Error::SshConnection("ssh process lock poisoned".into()))?
+                .try_wait()?;
+            if let Some(status) = status {
+                let log = fs::read_to_string(log_path).unwrap_or_default();
+                return Err(Error::SshConnection(format!(
+                    "ssh exited with {status} - {}",
+                    log.trim()
+                )));
+            }
+
+            if Instant::now() >= deadline {
+                return Err(Error::SshConnection(format!(
+                    "timed out after {timeout:?} waiting for the tunnel"
+                )));
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Path to the local end of the tunnel.
+    pub fn socket_path(&self) -> &Path {
+        &self.local_socket
+    }
+}
+
+impl Drop for SshTunnel {
+    fn drop(&mut self) {
+        if let Ok(child) = self.child.get_mut() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SshDestination;
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
+
+    #[test]
+    fn parses_ssh_destination() {
+        let dest =
+            SshDestination::parse("ssh://core@localhost:45678/run/user/1000/podman/podman.sock")
+                .unwrap();
+        assert_eq!(
+            dest,
+            SshDestination {
+                user: Some("core".into()),
+                host: "localhost".into(),
+                port: Some(45678),
+                socket_path: Some(PathBuf::from("/run/user/1000/podman/podman.sock")),
+                identity: None,
+                secure: false,
+                connect_timeout: Duration::from_secs(30),
+            }
+        );
+
+        let dest =
+            SshDestination::parse("ssh://root@[::1]/run/podman/podman.sock?secure=True").unwrap();
+        assert_eq!(dest.host, "::1");
+        assert_eq!(dest.port, None);
+        assert!(dest.secure);
+
+        let dest = SshDestination::parse("ssh://build-host").unwrap();
+        assert_eq!(dest.user, None);
+        assert_eq!(dest.socket_path, None);
+
+        assert!(SshDestination::parse("ssh://").is_err());
+        assert!(SshDestination::parse("ssh://host/sock?secure=maybe").is_err());
+    }
+
+    #[test]
+    fn builds_forward_args() {
+        let dest = SshDestination::parse("ssh://core@host:2222/run/podman/podman.sock?secure=true")
+            .unwrap()
+            .identity(Some(PathBuf::from("/home/core/.ssh/id_ed25519")))
+            .connect_timeout(Some(Duration::from_millis(1500)));
+        let args = dest.forward_args(
+            Path::new("/tmp/local.sock"),
+            Path::new("/run/podman/podman.sock"),
+        );
+
+        assert!(args.contains(&"StrictHostKeyChecking=yes".to_string()));
+        assert!(args.contains(&"ConnectTimeout=2".to_string()));
+        assert!(args.windows(2).any(|w| w == ["-p", "2222"]));
+        assert!(args
+            .windows(2)
+            .any(|w| w == ["-i", "/home/core/.ssh/id_ed25519"]));
+        assert!(args
+            .windows(2)
+            .any(|w| w == ["-L", "/tmp/local.sock:/run/podman/podman.sock"]));
+        assert_eq!(args.last().unwrap(), "core@host");
+    }
+}
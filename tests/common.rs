@@ -1,7 +1,6 @@
 #![allow(dead_code, unused_imports)]
 
 use std::env;
-use std::path::PathBuf;
 
 pub use futures_util::{StreamExt, TryStreamExt};
 pub use podman_api::{api, conn, models, opts, Podman};
@@ -19,27 +18,9 @@ pub fn init_runtime() -> Podman {
     if let Ok(uri) = env::var(URI_ENV_VAR) {
         Podman::new(uri).unwrap()
     } else {
-        #[cfg(unix)]
-        {
-            let uid = nix::unistd::Uid::effective();
-            let podman_dir = PathBuf::from(format!("/run/user/{uid}/podman"));
-            let podman_root_dir = PathBuf::from("/run/podman");
-            if podman_dir.exists() {
-                Podman::unix(podman_dir.join("podman.sock"))
-            } else if podman_root_dir.exists() {
-                Podman::unix(podman_root_dir.join("podman.sock"))
-            } else {
-                panic!(
-                    "Podman socket not found. Tried {URI_ENV_VAR} env variable, {} and {}",
-                    podman_dir.display(),
-                    podman_root_dir.display()
-                );
-            }
-        }
-        #[cfg(not(unix))]
-        {
-            panic!("Podman socket not found. Try setting the {URI_ENV_VAR} env variable",);
-        }
+        Podman::from_env().unwrap_or_else(|e| {
+            panic!("Podman socket not found. Try setting the {URI_ENV_VAR} env variable - {e}")
+        })
     }
 }
 
@@ -110,16 +91,7 @@ pub async fn create_base_image(
             .build()
     });
 
-    let mut image_stream = images.build(&opts).expect("image build stream");
-    let mut last = None;
-    while let Some(chunk) = image_stream.next().await {
-        assert!(chunk.is_ok());
-        last = Some(chunk);
-    }
-
-    podman
-        .images()
-        .get(last.unwrap().unwrap().stream.trim_end().to_string())
+    images.build_and_wait(&opts).await.expect("built image")
 }
 
 pub async fn get_image_full_id(podman: &Podman, name: &str) -> String {
@@ -4,10 +4,14 @@ use common::{
     cleanup_container,
     conn::TtyChunk,
     create_base_container, get_container_full_id, init_runtime,
-    models::ContainerStatus,
-    opts::{ContainerCreateOpts, ContainerWaitOpts, ExecCreateOpts},
+    models::{self, ContainerStatus},
+    opts::{
+        ContainerCopyOpts, ContainerCreateOpts, ContainerRestoreOpts, ContainerRunOpts,
+        ContainerUpdateOpts, ContainerWaitOpts, ExecCreateOpts, ExecOutputOpts, PullPolicy,
+    },
     StreamExt, TryStreamExt, DEFAULT_CMD, DEFAULT_CMD_ARRAY, DEFAULT_IMAGE,
 };
+use podman_api::api::SessionEnd;
 use podman_api::opts::ExecStartOpts;
 
 #[tokio::test]
@@ -42,7 +46,13 @@ async fn container_inspect() {
     cleanup_container(&podman, container_name).await;
     // check that the container got correctly removed
     let inspect_result = container.inspect().await;
-    assert!(inspect_result.is_err());
+    match inspect_result.unwrap_err() {
+        podman_api::Error::NotFound { resource, id, .. } => {
+            assert_eq!(resource, Some(podman_api::api::ApiResource::Containers));
+            assert_eq!(id.as_deref(), Some(container_name));
+        }
+        e => panic!("expected Error::NotFound, got {e}"),
+    }
 }
 
 #[tokio::test]
@@ -377,8 +387,41 @@ async fn container_checkpoint() {
     assert!(!state.checkpointed.unwrap_or_default());
     assert!(state.restored.unwrap_or_default());
 
-    // TODO: test checkpoint_export and restore_import
+    let tmp = tempdir::TempDir::new("test-checkpoint").expect("temporary dir");
+    let archive_path = tmp.path().join("checkpoint.tar.gz");
+    let exported = container
+        .checkpoint_to_path(&archive_path, &Default::default())
+        .await
+        .expect("exported checkpoint");
+    assert!(exported.size > 0);
+    assert_eq!(
+        std::fs::metadata(&archive_path).unwrap().len(),
+        exported.size
+    );
+
+    let restored_name = "test-checkpoint-restored-container";
+    let archive = futures_util::io::Cursor::new(std::fs::read(&archive_path).unwrap());
+    let report = podman
+        .containers()
+        .restore_from_archive(
+            archive,
+            &ContainerRestoreOpts::builder()
+                .name(restored_name)
+                .print_stats(true)
+                .build(),
+        )
+        .await
+        .expect("restored container from archive");
+    assert_ne!(
+        report.id,
+        get_container_full_id(&podman, container_name).await
+    );
+    assert_eq!(
+        report.id,
+        get_container_full_id(&podman, restored_name).await
+    );
 
+    cleanup_container(&podman, restored_name).await;
     cleanup_container(&podman, container_name).await;
 }
 
@@ -413,7 +456,8 @@ async fn container_exec() {
     let exec_inspect_result = exec.inspect().await;
     assert!(exec_inspect_result.is_ok());
     let exec_inspect_data = exec_inspect_result.unwrap();
-    assert_eq!(exec_inspect_data.get("ExitCode").unwrap(), 0);
+    assert_eq!(exec_inspect_data.exit_code, Some(0));
+    assert_eq!(exec_inspect_data.running, Some(false));
 
     let exec_result = container
         .create_exec(
@@ -454,6 +498,127 @@ async fn container_exec() {
     cleanup_container(&podman, container_name).await;
 }
 
+#[tokio::test]
+async fn container_exec_output() {
+    let podman = init_runtime();
+
+    let container_name = "test-exec-output-container";
+    let container = create_base_container(&podman, container_name, None).await;
+
+    let _ = container.start(None).await;
+
+    let output = container
+        .exec_output(
+            ["bash", "-c", "echo out; echo err >&2; exit 3"],
+            &ExecOutputOpts::builder().build(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(output.exit_code, 3);
+    assert_eq!(output.stdout, b"out\n");
+    assert_eq!(output.stderr, b"err\n");
+    assert!(!output.truncated);
+
+    let output = container
+        .exec_output(
+            ["tr", "a-z", "A-Z"],
+            &ExecOutputOpts::builder().stdin("hello").build(),
+        )
+        .await
+        .unwrap();
+    assert!(output.success());
+    assert_eq!(output.stdout, b"HELLO");
+
+    let output = container
+        .exec_output(
+            ["bash", "-c", "echo 1234567890"],
+            &ExecOutputOpts::builder().tty(true).output_limit(4).build(),
+        )
+        .await
+        .unwrap();
+    assert!(output.success());
+    assert_eq!(output.stdout, b"1234");
+    assert!(output.truncated);
+
+    let output = container
+        .exec_output(
+            ["sleep", "5"],
+            &ExecOutputOpts::builder()
+                .timeout(std::time::Duration::from_millis(500))
+                .build(),
+        )
+        .await;
+    assert!(output.unwrap_err().is_timeout());
+
+    cleanup_container(&podman, container_name).await;
+}
+
+#[tokio::test]
+async fn container_exec_interactive() {
+    let podman = init_runtime();
+
+    let container_name = "test-exec-interactive-container";
+    let container = create_base_container(&podman, container_name, None).await;
+
+    let _ = container.start(None).await;
+
+    let exec = container
+        .create_exec(
+            &ExecCreateOpts::builder()
+                .attach_stdin(true)
+                .attach_stdout(true)
+                .attach_stderr(true)
+                .command(["cat"])
+                .build(),
+        )
+        .await
+        .unwrap();
+    let session = exec.start_interactive(&Default::default()).await.unwrap();
+    let mut stdout = futures_util::io::Cursor::new(vec![]);
+    let end = session
+        .run(
+            &b"hello"[..],
+            &mut stdout,
+            futures_util::io::sink(),
+            futures_util::stream::empty(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(end, SessionEnd::Exited);
+    assert_eq!(stdout.into_inner(), b"hello");
+    assert_eq!(exec.wait().await.unwrap(), 0);
+
+    let exec = container
+        .create_exec(
+            &ExecCreateOpts::builder()
+                .attach_stdin(true)
+                .attach_stdout(true)
+                .tty(true)
+                .command(["sleep", "inf"])
+                .build(),
+        )
+        .await
+        .unwrap();
+    let session = exec
+        .start_interactive(&ExecStartOpts::builder().tty(true).build())
+        .await
+        .unwrap()
+        .detach_keys("ctrl-x")
+        .unwrap();
+    let end = session
+        .run(
+            &b"\x18"[..],
+            futures_util::io::sink(),
+            futures_util::io::sink(),
+            futures_util::stream::iter([(120, 40)]),
+        )
+        .await
+        .unwrap();
+    assert_eq!(end, SessionEnd::Detached);
+
+    cleanup_container(&podman, container_name).await;
+}
+
 #[tokio::test]
 async fn container_exec_detach() {
     let podman = init_runtime();
@@ -479,6 +644,11 @@ async fn container_exec_detach() {
         .await;
     assert!(exec_result.is_ok());
     let exec = exec_result.unwrap();
+    // an exec that was never started has no exit code
+    assert!(matches!(
+        exec.wait().await,
+        Err(podman_api::Error::ExecNotStarted(_))
+    ));
     let opts = ExecStartOpts::builder().detach(true).build();
     let exec_stream = exec.start(&opts).await.unwrap();
     assert!(exec_stream.is_none());
@@ -486,7 +656,15 @@ async fn container_exec_detach() {
     let exec_inspect_result = exec.inspect().await;
     assert!(exec_inspect_result.is_ok());
     let exec_inspect_data = exec_inspect_result.unwrap();
-    assert_eq!(exec_inspect_data.get("ExitCode").unwrap(), 0);
+    assert_eq!(
+        exec_inspect_data.container_id.as_deref(),
+        Some(
+            get_container_full_id(&podman, container_name)
+                .await
+                .as_str()
+        )
+    );
+    assert_eq!(exec.wait().await.unwrap(), 0);
 
     let exec_result = container
         .create_exec(
@@ -608,6 +786,195 @@ async fn container_copy_file_into() {
     cleanup_container(&podman, container_name).await;
 }
 
+#[tokio::test]
+async fn container_copy_to_from_stream() {
+    let podman = init_runtime();
+
+    let container_name = "test-copy-to-stream-container";
+    let container = create_base_container(&podman, container_name, None).await;
+    let _ = container.start(None).await;
+
+    let archive = |name: &str| {
+        let data = vec![7u8; 256 * 1024];
+        let mut ar = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o0644);
+        ar.append_data(&mut header, name, &data[..]).unwrap();
+        ar.into_inner().unwrap()
+    };
+
+    let data = archive("from-stream");
+    let len = data.len() as u64;
+    let chunks = data
+        .chunks(16 * 1024)
+        .map(|chunk| Ok(bytes::Bytes::copy_from_slice(chunk)))
+        .collect::<Vec<_>>();
+    let sent = container
+        .copy_to_from_stream("/tmp", futures_util::stream::iter(chunks))
+        .await
+        .expect("copied archive stream");
+    assert_eq!(sent, len);
+
+    let data = archive("from-reader");
+    let sent = container
+        .copy_to_from_reader("/tmp", futures_util::io::Cursor::new(data))
+        .await
+        .expect("copied archive reader");
+    assert_eq!(sent, len);
+
+    let data = archive("with-progress");
+    let chunks = data
+        .chunks(16 * 1024)
+        .map(|chunk| Ok(bytes::Bytes::copy_from_slice(chunk)))
+        .collect::<Vec<_>>();
+    let progress = container
+        .copy_to_with_progress("/tmp", futures_util::stream::iter(chunks))
+        .try_collect::<Vec<_>>()
+        .await
+        .expect("copy progress");
+    let last = progress.last().expect("final progress");
+    assert!(last.finished);
+    assert_eq!(last.bytes_sent, len);
+    assert!(progress
+        .windows(2)
+        .all(|w| w[0].bytes_sent <= w[1].bytes_sent));
+
+    for name in ["from-stream", "from-reader", "with-progress"] {
+        let stat = container
+            .stat_path(format!("/tmp/{name}"))
+            .await
+            .expect("stat of copied file");
+        assert_eq!(stat.size, 256 * 1024);
+    }
+
+    cleanup_container(&podman, container_name).await;
+}
+
+#[tokio::test]
+async fn container_stat_path() {
+    let podman = init_runtime();
+
+    let container_name = "test-stat-path-container";
+    let container = create_base_container(&podman, container_name, None).await;
+    let _ = container.start(None).await;
+
+    let stat = container.stat_path("/etc").await.expect("stat of /etc");
+    assert_eq!(stat.name, "etc");
+    assert!(stat.is_dir());
+
+    let stat = container
+        .stat_path("/etc/hostname")
+        .await
+        .expect("stat of /etc/hostname");
+    assert!(stat.is_file());
+    assert!(stat.size > 0);
+
+    let stat = container.stat_path("/bin").await.expect("stat of /bin");
+    assert!(stat.is_symlink());
+    assert_eq!(stat.link_target, "usr/bin");
+
+    let missing = container.stat_path("/missing").await;
+    assert!(missing.unwrap_err().is_not_found());
+
+    cleanup_container(&podman, container_name).await;
+}
+
+#[tokio::test]
+async fn container_copy_paths() {
+    use std::os::unix::fs::{symlink, PermissionsExt};
+
+    let podman = init_runtime();
+
+    let container_name = "test-copy-paths-container";
+    let container = create_base_container(&podman, container_name, None).await;
+    let _ = container.start(None).await;
+
+    let tmp = tempdir::TempDir::new("test-copy-paths").expect("temporary dir");
+    let src = tmp.path().join("app");
+    std::fs::create_dir_all(src.join("bin")).unwrap();
+    std::fs::write(src.join("bin/run.sh"), "#!/bin/sh").unwrap();
+    std::fs::set_permissions(
+        src.join("bin/run.sh"),
+        std::fs::Permissions::from_mode(0o750),
+    )
+    .unwrap();
+    symlink("bin/run.sh", src.join("run")).unwrap();
+    let opts = ContainerCopyOpts::builder().build();
+
+    // the directory doesn't exist in the container so it becomes /tmp/copied
+    container
+        .copy_path_to_container(&src, "/tmp/copied", &opts)
+        .await
+        .expect("copied directory into the container");
+    // /tmp exists so the directory is copied into it
+    container
+        .copy_path_to_container(&src, "/tmp", &opts)
+        .await
+        .expect("copied directory into the container");
+    assert!(container
+        .copy_path_to_container(&src, "/tmp/missing/", &opts)
+        .await
+        .is_ok());
+    assert!(container
+        .copy_path_to_container(src.join("bin/run.sh"), "/tmp/missing-dir/", &opts)
+        .await
+        .is_err());
+
+    let dest = tmp.path().join("out");
+    container
+        .copy_path_from_container("/tmp/copied", &dest, &opts)
+        .await
+        .expect("copied directory out of the container");
+    assert_eq!(
+        std::fs::read_to_string(dest.join("bin/run.sh")).unwrap(),
+        "#!/bin/sh"
+    );
+    let mode = std::fs::metadata(dest.join("bin/run.sh"))
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o750);
+    assert_eq!(
+        std::fs::read_link(dest.join("run")).unwrap(),
+        std::path::Path::new("bin/run.sh")
+    );
+
+    // dest exists now so the directory is copied into it, only the content with `/.`
+    container
+        .copy_path_from_container("/tmp/app", &dest, &opts)
+        .await
+        .unwrap();
+    assert!(dest.join("app/bin/run.sh").exists());
+    let contents = tmp.path().join("contents");
+    std::fs::create_dir(&contents).unwrap();
+    container
+        .copy_path_from_container("/tmp/app/.", &contents, &opts)
+        .await
+        .unwrap();
+    assert!(contents.join("bin/run.sh").exists());
+
+    // a file is copied into a directory, a directory doesn't replace a file unless asked to
+    container
+        .copy_path_from_container("/tmp/app/bin/run.sh", &contents, &opts)
+        .await
+        .unwrap();
+    assert!(contents.join("run.sh").is_file());
+    let overwrite = ContainerCopyOpts::builder().overwrite(true).build();
+    std::fs::write(contents.join("conflict"), "").unwrap();
+    assert!(container
+        .copy_path_from_container("/tmp/app/bin", contents.join("conflict"), &opts)
+        .await
+        .is_err());
+    container
+        .copy_path_from_container("/tmp/app/bin", contents.join("conflict"), &overwrite)
+        .await
+        .unwrap();
+    assert!(contents.join("conflict/run.sh").exists());
+
+    cleanup_container(&podman, container_name).await;
+}
+
 #[tokio::test]
 async fn container_changes() {
     let podman = init_runtime();
@@ -729,6 +1096,30 @@ async fn container_stats() {
     cleanup_container(&podman, container_name).await;
 }
 
+#[tokio::test]
+async fn container_update() {
+    let podman = init_runtime();
+
+    let container_name = "test-update-container";
+    let container = create_base_container(&podman, container_name, None).await;
+    let _ = container.start(None).await;
+
+    let opts = ContainerUpdateOpts::builder()
+        .resource_limits(
+            serde_json::from_value(serde_json::json!({ "memory": { "limit": 256 * 1024 * 1024 } }))
+                .unwrap(),
+        )
+        .pids(models::LinuxPids { limit: Some(64) })
+        .build();
+    assert!(container.update(&opts).await.is_ok());
+
+    let host_config = container.inspect().await.unwrap().host_config.unwrap();
+    assert_eq!(host_config.memory, Some(256 * 1024 * 1024));
+    assert_eq!(host_config.pids_limit, Some(64));
+
+    cleanup_container(&podman, container_name).await;
+}
+
 #[tokio::test]
 async fn container_top() {
     use podman_api::opts::ContainerTopOpts;
@@ -1066,3 +1457,78 @@ async fn container_wait() {
     assert!(wait_result.is_ok());
     cleanup_container(&podman, container_name).await;
 }
+
+#[tokio::test]
+async fn container_wait_exit_code() {
+    let podman = init_runtime();
+    let container_name = "test-wait-exit-code-container";
+
+    let container = create_base_container(
+        &podman,
+        container_name,
+        Some(
+            ContainerCreateOpts::builder()
+                .name(container_name)
+                .image(DEFAULT_IMAGE)
+                .command(["sh", "-c", "exit 3"])
+                .build(),
+        ),
+    )
+    .await;
+    container.start(None).await.expect("started container");
+
+    let exit_code = container
+        .wait_for_condition(ContainerStatus::Exited, &ContainerWaitOpts::default())
+        .await
+        .expect("waited for container");
+    assert_eq!(exit_code, 3);
+
+    let wait_result = podman
+        .containers()
+        .get("test-wait-nonexistent-container")
+        .wait(
+            &ContainerWaitOpts::builder()
+                .timeout(std::time::Duration::from_secs(5))
+                .build(),
+        )
+        .await;
+    assert!(wait_result.unwrap_err().is_not_found());
+
+    cleanup_container(&podman, container_name).await;
+}
+
+#[tokio::test]
+async fn containers_run() {
+    let podman = init_runtime();
+    let container_name = "test-run-container";
+    cleanup_container(&podman, container_name).await;
+
+    let run = podman
+        .containers()
+        .run(
+            &ContainerRunOpts::builder(
+                ContainerCreateOpts::builder()
+                    .name(container_name)
+                    .image(DEFAULT_IMAGE)
+                    .command(["sh", "-c", "echo out; echo err >&2; exit 2"])
+                    .build(),
+            )
+            .pull_policy(PullPolicy::Missing)
+            .remove(true)
+            .build(),
+        )
+        .await
+        .expect("started container");
+
+    let (mut stdout, mut stderr) = (vec![], vec![]);
+    let exit_code = run
+        .output(&mut stdout, &mut stderr)
+        .await
+        .expect("collected container output");
+    assert_eq!(exit_code, 2);
+    assert_eq!(stdout, b"out\n");
+    assert_eq!(stderr, b"err\n");
+
+    let exists_result = podman.containers().get(container_name).exists().await;
+    assert!(!exists_result.unwrap());
+}
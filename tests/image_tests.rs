@@ -2,7 +2,7 @@ mod common;
 
 use common::{
     create_base_image, get_image_full_id, init_runtime, models, opts, tempdir_with_dockerfile,
-    TryStreamExt, DEFAULT_IMAGE, TEST_IMAGE_PATH,
+    TempDir, TryStreamExt, DEFAULT_IMAGE, TEST_IMAGE_PATH,
 };
 
 #[tokio::test]
@@ -139,6 +139,36 @@ async fn image_tag_untag() {
     let _ = image.remove().await;
 }
 
+#[tokio::test]
+async fn image_tag_reference() {
+    let podman = init_runtime();
+    let images = podman.images();
+
+    let image_name = "test-tag-reference-image";
+    create_base_image(&podman, image_name, None).await;
+
+    let reference: opts::ImageReference = format!("localhost/{image_name}")
+        .parse()
+        .expect("valid reference");
+    let image = images.get(&reference);
+
+    let tagged = reference.with_tag("2.0.0").expect("valid tag");
+    let opts = opts::ImageTagOpts::builder().reference(&tagged).build();
+    assert!(image.tag(&opts).await.is_ok());
+
+    let repo_tags = images
+        .get(&tagged)
+        .inspect()
+        .await
+        .expect("image inspect data")
+        .repo_tags
+        .expect("repo tags");
+    assert!(repo_tags.contains(&tagged.to_string()));
+
+    //cleanup
+    let _ = image.remove().await;
+}
+
 #[tokio::test]
 async fn image_export_import() {
     let podman = init_runtime();
@@ -183,6 +213,110 @@ async fn image_export_import() {
     assert!(!image.exists().await.unwrap());
 }
 
+#[tokio::test]
+async fn image_export_load_streaming() {
+    let podman = init_runtime();
+    let images = podman.images();
+
+    let image_name = "test-load-stream-image";
+    create_base_image(&podman, image_name, None).await;
+
+    let image = images.get(image_name);
+
+    let export_data = image
+        .export(&Default::default())
+        .try_concat()
+        .await
+        .expect("image archive");
+    let tmp = TempDir::new("load-stream").expect("temp dir");
+    let archive_path = tmp.path().join("image.tar");
+    std::fs::write(&archive_path, &export_data).expect("write archive");
+
+    let _ = image.remove().await;
+    assert!(!image.exists().await.unwrap());
+
+    let load_result = images.load_from_path(&archive_path).await;
+    assert!(load_result.is_ok());
+    assert!(image.exists().await.unwrap());
+
+    let _ = image.remove().await;
+    assert!(!image.exists().await.unwrap());
+
+    let reader = futures_util::io::Cursor::new(export_data);
+    let load_result = images.load_from_reader(reader).await;
+    assert!(load_result.is_ok());
+    assert!(image.exists().await.unwrap());
+
+    let import_opts = opts::ImageImportOpts::builder()
+        .reference(image_name)
+        .build();
+    let import_result = images
+        .import_from_path(&import_opts, tmp.path().join("missing.tar"))
+        .await;
+    assert!(import_result.is_err());
+
+    let _ = image.remove().await;
+}
+
+#[tokio::test]
+async fn image_export_to_path() {
+    let podman = init_runtime();
+    let images = podman.images();
+
+    let image_name = "test-export-path-image";
+    let image = create_base_image(&podman, image_name, None).await;
+    let tmp = TempDir::new("export-path").expect("temp dir");
+
+    let archive_path = tmp.path().join("image.tar");
+    let exported = image
+        .export_to_path(
+            &opts::ImageExportOpts::builder()
+                .format(opts::ImageExportFormat::OciArchive)
+                .build(),
+            &archive_path,
+        )
+        .await
+        .expect("exported archive");
+    let written = std::fs::read(&archive_path).expect("archive");
+    assert_eq!(exported.size, written.len() as u64);
+    assert!(exported.verify(&exported.digest).is_ok());
+
+    let archive = podman_api::oci::ImageArchive::open(&archive_path).expect("oci archive");
+    let archived = archive.images().expect("archived images");
+    assert_eq!(archived.len(), 1);
+    for layer in &archived[0].manifest.layers {
+        assert!(!archive
+            .layer_entries(&layer.digest)
+            .expect("layer entries")
+            .is_empty());
+    }
+
+    let dir_path = tmp.path().join("image");
+    image
+        .export_to_path(
+            &opts::ImageExportOpts::builder()
+                .format(opts::ImageExportFormat::OciDir)
+                .build(),
+            &dir_path,
+        )
+        .await
+        .expect("exported directory");
+    assert!(dir_path.join("index.json").exists());
+
+    let exported = images
+        .export_to_path(
+            &opts::ImagesExportOpts::builder()
+                .references([image_name])
+                .build(),
+            tmp.path().join("images.tar"),
+        )
+        .await
+        .expect("exported archive");
+    assert!(exported.size > 0);
+
+    let _ = image.remove().await;
+}
+
 #[tokio::test]
 async fn image_search() {
     let podman = init_runtime();
@@ -380,3 +514,119 @@ async fn image_list() {
     let _ = image_a.remove().await;
     let _ = image_b.remove().await;
 }
+
+#[tokio::test]
+async fn image_build_with_context() {
+    let podman = init_runtime();
+    let images = podman.images();
+    let tag = "test-build-context-image";
+    let _ = images.get(tag).remove().await;
+
+    let context = opts::BuildContext::builder()
+        .dockerfile(format!(
+            "FROM {DEFAULT_IMAGE}\nCOPY data.txt {TEST_IMAGE_PATH}\nCMD sleep inf"
+        ))
+        .file("data.txt", "1234")
+        .build()
+        .expect("in-memory build context");
+    let opts = opts::ImageBuildOpts::context_builder().tag(tag).build();
+
+    let reports = images
+        .build_with_context(context, &opts)
+        .try_collect::<Vec<_>>()
+        .await;
+    assert!(reports.is_ok());
+    assert!(images.get(tag).exists().await.unwrap());
+
+    let _ = images.get(tag).remove().await;
+
+    let tmp = tempdir_with_dockerfile(tag, None);
+    let mut archive = tar::Builder::new(Vec::new());
+    archive.append_dir_all(".", tmp.path()).unwrap();
+    let context =
+        opts::BuildContext::reader(futures_util::io::Cursor::new(archive.into_inner().unwrap()));
+
+    let reports = images
+        .build_with_context(context, &opts)
+        .try_collect::<Vec<_>>()
+        .await;
+    assert!(reports.is_ok());
+    assert!(images.get(tag).exists().await.unwrap());
+
+    let _ = images.get(tag).remove().await;
+}
+
+#[tokio::test]
+async fn image_build_events() {
+    let podman = init_runtime();
+    let images = podman.images();
+    let tag = "test-build-events-image";
+    let _ = images.get(tag).remove().await;
+
+    let tmp = tempdir_with_dockerfile(tag, None);
+    let opts = opts::ImageBuildOpts::builder(tmp.path().to_string_lossy())
+        .tag(tag)
+        .build();
+    let events = images
+        .build_events(&opts)
+        .expect("build events stream")
+        .try_collect::<Vec<_>>()
+        .await
+        .expect("build events");
+
+    assert!(events.contains(&models::BuildEvent::Step {
+        index: 1,
+        total: 4,
+        instruction: format!("FROM {DEFAULT_IMAGE}"),
+    }));
+    assert!(events.contains(&models::BuildEvent::Output("321".into())));
+    let Some(models::BuildEvent::ImageId(id)) = events.last() else {
+        panic!("expected the build to end with the image id");
+    };
+    assert_eq!(&get_image_full_id(&podman, tag).await, id);
+
+    let failed = images
+        .build_and_wait(
+            &opts::ImageBuildOpts::builder(
+                tempdir_with_dockerfile(tag, Some("FROM scratch\nRUN exit 1"))
+                    .path()
+                    .to_string_lossy(),
+            )
+            .build(),
+        )
+        .await;
+    assert!(failed.is_err());
+
+    let _ = images.get(tag).remove().await;
+}
+
+#[tokio::test]
+async fn image_pull_progress() {
+    let podman = init_runtime();
+    let images = podman.images();
+
+    let progress = images
+        .pull_progress(
+            &opts::PullOpts::builder()
+                .reference(DEFAULT_IMAGE)
+                .policy(opts::PullPolicy::Always)
+                .build(),
+        )
+        .try_collect::<Vec<_>>()
+        .await
+        .expect("pull progress");
+    let last = progress.last().expect("pull progress updates");
+    assert!(last.finished);
+    assert!(!last.image_ids.is_empty());
+    assert_eq!(last.fraction(), 1.0);
+
+    let failed = images
+        .pull_progress(
+            &opts::PullOpts::builder()
+                .reference("localhost:1/does-not-exist:latest")
+                .build(),
+        )
+        .try_collect::<Vec<_>>()
+        .await;
+    assert!(failed.is_err());
+}
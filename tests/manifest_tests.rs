@@ -1,6 +1,8 @@
 mod common;
 
-use common::{api::Manifest, init_runtime, opts, Podman, DEFAULT_IMAGE};
+use common::{
+    api::Manifest, init_runtime, models, opts, Podman, TryStreamExt, DEFAULT_IMAGE, TEST_IMAGE_PATH,
+};
 
 //####################################################################################################
 
@@ -104,3 +106,83 @@ async fn manifest_remove_image() {
     let _ = manifest.delete().await;
     let _ = new_manifest.delete().await;
 }
+
+#[tokio::test]
+async fn manifest_build_multi_arch() {
+    let podman = init_runtime();
+    let manifest_name = "test-build-multi-arch-manifest";
+    let _ = podman.manifests().get(manifest_name).delete().await;
+
+    let context = || {
+        opts::BuildContext::builder()
+            .dockerfile(format!(
+                "FROM {DEFAULT_IMAGE}\nCOPY data.txt {TEST_IMAGE_PATH}"
+            ))
+            .file("data.txt", "1234")
+            .build()
+            .expect("in-memory build context")
+    };
+    let platforms = [
+        opts::Platform::new("linux").arch("amd64"),
+        opts::Platform::new("linux").arch("arm64").version("v8"),
+    ];
+
+    for parallel in [false, true] {
+        let opts = opts::ManifestBuildOpts::builder(manifest_name)
+            .platforms(platforms.clone())
+            .parallel(parallel)
+            .build();
+        let events = podman
+            .manifests()
+            .build(context(), &opts)
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("build events");
+
+        for platform in &platforms {
+            assert!(events.iter().any(|event| matches!(
+                event,
+                models::ManifestBuildEvent::Built { platform: built, .. } if built == platform
+            )));
+        }
+        let added = events
+            .iter()
+            .filter_map(|event| match event {
+                models::ManifestBuildEvent::Added { platform, .. } => Some(platform),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(added, platforms.iter().collect::<Vec<_>>());
+
+        let manifest = podman.manifests().get(manifest_name);
+        let list = manifest.inspect().await.expect("manifest inspect data");
+        let mut architectures = list
+            .manifests
+            .unwrap()
+            .into_iter()
+            .filter_map(|descriptor| descriptor.platform?.architecture)
+            .collect::<Vec<_>>();
+        architectures.sort();
+        assert_eq!(architectures, vec!["amd64", "arm64"]);
+        let _ = manifest.delete().await;
+    }
+
+    let failing = opts::BuildContext::builder()
+        .dockerfile("FROM scratch\nCOPY missing.txt /missing.txt")
+        .build()
+        .unwrap();
+    let opts = opts::ManifestBuildOpts::builder(manifest_name)
+        .platforms(platforms)
+        .build();
+    assert!(podman
+        .manifests()
+        .build_and_wait(failing, &opts)
+        .await
+        .is_err());
+    assert!(!podman
+        .manifests()
+        .get(manifest_name)
+        .exists()
+        .await
+        .unwrap());
+}
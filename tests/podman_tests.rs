@@ -40,6 +40,32 @@ async fn podman_version() {
     assert_eq!(ping_data.api_version, version_data.api_version.unwrap());
 }
 
+#[tokio::test]
+async fn podman_capabilities() {
+    let podman = init_runtime();
+
+    let capabilities = podman.capabilities().await.unwrap();
+    let ping_data = podman.ping().await.unwrap();
+    assert_eq!(
+        capabilities.server_api_version.to_string(),
+        ping_data.libpod_api_version
+    );
+    assert!(capabilities.api_version <= podman_api::LATEST_API_VERSION);
+    assert!(capabilities.supports((4, 0)));
+    assert!(matches!(
+        capabilities.require((99, 0)),
+        Err(podman_api::Error::UnsupportedByServer { .. })
+    ));
+
+    let uri = std::env::var("PODMAN_API_URI")
+        .unwrap_or_else(|_| podman_api::config::default_connection().unwrap().uri);
+    let negotiated = podman_api::Podman::builder(uri)
+        .negotiate_version(true)
+        .build()
+        .unwrap();
+    assert!(negotiated.info().await.is_ok());
+}
+
 #[tokio::test]
 async fn podman_data_usage() {
     let podman = init_runtime();
@@ -0,0 +1,35 @@
+#![cfg(all(unix, feature = "ssh"))]
+
+mod common;
+
+use common::Podman;
+
+/// URI of an SSH destination forwarding to a podman (or stand-in) socket, for example
+/// `ssh://user@localhost:2222/run/user/1000/podman/podman.sock`.
+const SSH_URI_ENV_VAR: &str = "PODMAN_API_SSH_URI";
+/// Optional identity file used to authenticate against `PODMAN_API_SSH_URI`.
+const SSH_KEY_ENV_VAR: &str = "PODMAN_API_SSH_KEY";
+
+#[tokio::test]
+async fn podman_ssh_ping() {
+    let Ok(uri) = std::env::var(SSH_URI_ENV_VAR) else {
+        eprintln!("skipping, {SSH_URI_ENV_VAR} is not set");
+        return;
+    };
+    let identity = std::env::var(SSH_KEY_ENV_VAR).ok();
+
+    let podman = Podman::ssh(uri, identity).expect("ssh tunnel");
+    let ping_result = podman.ping().await;
+    assert!(ping_result.is_ok());
+
+    // the tunnel must outlive the original instance as long as a clone exists
+    let clone = podman.clone();
+    drop(podman);
+    assert!(clone.ping().await.is_ok());
+}
+
+#[tokio::test]
+async fn podman_ssh_invalid_destination() {
+    let result = Podman::new("ssh://");
+    assert!(result.is_err());
+}